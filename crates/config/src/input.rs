@@ -1,14 +1,192 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use serde::Deserialize;
-use validator::Validate;
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
 
 use super::helper::absolute_path;
+use super::output::Tag;
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+#[validate(schema(function = "has_source"))]
 pub struct Input {
+    /// a source with nothing of its own, for the common case of one input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     #[validate(custom(function = "absolute_path"))]
-    pub path: PathBuf,
+    pub path: Option<PathBuf>,
+
+    /// used by sources without their own
     pub exts: Vec<String>,
+
+    /// used by sources without their own
     pub excludes: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[validate(nested)]
+    pub sources: Vec<Source>,
+}
+
+/// an input dir, values not set are taken from `[input]` and `[output]`
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct Source {
+    #[validate(custom(function = "absolute_path"))]
+    pub path: PathBuf,
+
+    pub exts: Option<Vec<String>>,
+
+    pub excludes: Option<Vec<String>>,
+
+    /// where videos of this source go
+    #[validate(custom(function = "absolute_path"))]
+    pub output: Option<PathBuf>,
+
+    #[validate(length(min = 1, message = "should have at least 1 rule"))]
+    pub rule: Option<Vec<Tag>>,
+}
+
+fn has_source(input: &Input) -> Result<(), ValidationError> {
+    if input.path.is_none() && input.sources.is_empty() {
+        let err = ValidationError::new("sources").with_message("should set path or sources".into());
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+impl Input {
+    /// dirs to find videos in
+    pub fn paths(&self) -> Vec<&Path> {
+        self.path
+            .as_deref()
+            .into_iter()
+            .chain(self.sources.iter().map(|source| source.path.as_path()))
+            .collect()
+    }
+
+    /// the innermost source containing the file, `None` for files in `path` or out of all
+    pub fn source_of(&self, file: &Path) -> Option<&Source> {
+        let source = self
+            .sources
+            .iter()
+            .filter(|source| file.starts_with(&source.path))
+            .max_by_key(|source| source.path.components().count())?;
+        // `path` wins if it is inside the source
+        match self.path {
+            Some(ref path) if file.starts_with(path) && path.starts_with(&source.path) => None,
+            _ => Some(source),
+        }
+    }
+
+    pub fn exts_of(&self, file: &Path) -> &[String] {
+        self.source_of(file)
+            .and_then(|source| source.exts.as_deref())
+            .unwrap_or(&self.exts)
+    }
+
+    pub fn excludes_of(&self, file: &Path) -> &[String] {
+        self.source_of(file)
+            .and_then(|source| source.excludes.as_deref())
+            .unwrap_or(&self.excludes)
+    }
+
+    /// exts of all sources, for finding videos already organized
+    pub fn all_exts(&self) -> Vec<&str> {
+        let mut exts = self
+            .exts
+            .iter()
+            .chain(
+                self.sources
+                    .iter()
+                    .flat_map(|source| source.exts.iter().flatten()),
+            )
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        exts.sort();
+        exts.dedup();
+
+        exts
+    }
+
+    /// whether the file is out of all sources, or in an excluded dir of its source
+    pub fn is_excluded(&self, file: &Path) -> bool {
+        let Some(root) = self
+            .paths()
+            .into_iter()
+            .filter(|path| file.starts_with(path))
+            .max_by_key(|path| path.components().count())
+        else {
+            return true;
+        };
+        let Ok(relative) = file.strip_prefix(root) else {
+            return true;
+        };
+
+        let excludes = self.excludes_of(file);
+        relative
+            .components()
+            .filter_map(|component| component.as_os_str().to_str())
+            .any(|name| excludes.iter().any(|e| e == name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn input() -> Input {
+        toml::from_str(
+            r#"
+            path = "/data/input"
+            exts = ["mp4"]
+            excludes = ["output"]
+
+            [[sources]]
+            path = "/data/a"
+            exts = ["mkv"]
+
+            [[sources]]
+            path = "/data/a/b"
+            excludes = ["skip"]
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_source_of() {
+        let input = input();
+        let cases = [
+            ("/data/input/x.mp4", None),
+            ("/data/a/x.mkv", Some("/data/a")),
+            ("/data/a/b/x.mkv", Some("/data/a/b")),
+            ("/other/x.mp4", None),
+        ];
+        for (file, expected) in cases {
+            let actual = input
+                .source_of(Path::new(file))
+                .map(|source| source.path.as_path());
+            assert_eq!(actual, expected.map(Path::new), "{file}");
+        }
+
+        assert_eq!(input.exts_of(Path::new("/data/a/x.mkv")), ["mkv"]);
+        assert_eq!(input.exts_of(Path::new("/data/a/b/x.mp4")), ["mp4"]);
+        assert_eq!(input.excludes_of(Path::new("/data/a/b/x.mp4")), ["skip"]);
+        assert_eq!(input.all_exts(), vec!["mkv", "mp4"]);
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        let input = input();
+        let cases = [
+            ("/data/input/x.mp4", false),
+            ("/data/input/output/x.mp4", true),
+            ("/data/a/output/x.mkv", true),
+            ("/data/a/b/output/x.mkv", false),
+            ("/data/a/b/skip/x.mkv", true),
+            ("/other/x.mp4", true),
+        ];
+        for (file, expected) in cases {
+            assert_eq!(input.is_excluded(Path::new(file)), expected, "{file}");
+        }
+    }
 }
@@ -0,0 +1,252 @@
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+use std::iter;
+
+use super::{Config, Method};
+
+/// something wrong in config, which is allowed by validation
+#[derive(Debug, PartialEq, Eq)]
+pub enum Issue {
+    Error(String),
+    Warning(String),
+}
+
+impl Issue {
+    pub fn is_error(&self) -> bool {
+        matches!(self, Issue::Error(_))
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::Error(msg) => write!(f, "error: {msg}"),
+            Issue::Warning(msg) => write!(f, "warning: {msg}"),
+        }
+    }
+}
+
+impl Config {
+    /// check the values together and against the filesystem
+    pub fn lint(&self) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        let input = &self.input;
+
+        for path in input.paths() {
+            if !path.exists() {
+                issues.push(Issue::Error(format!(
+                    "input path {} does not exist",
+                    path.display()
+                )));
+            } else if !path.is_dir() {
+                issues.push(Issue::Error(format!(
+                    "input path {} is not a dir",
+                    path.display()
+                )));
+            }
+        }
+
+        for library in self.libraries() {
+            if !library.exists() {
+                issues.push(Issue::Warning(format!(
+                    "output path {} does not exist, it will be created",
+                    library.display()
+                )));
+            } else if !library.is_dir() {
+                issues.push(Issue::Error(format!(
+                    "output path {} is not a dir",
+                    library.display()
+                )));
+            }
+
+            // videos in output would be found again in the next run
+            for path in input.paths() {
+                let Ok(relative) = library.strip_prefix(path) else {
+                    continue;
+                };
+                let excludes = input.excludes_of(path);
+                let excluded = relative
+                    .components()
+                    .filter_map(|component| component.as_os_str().to_str())
+                    .any(|name| excludes.iter().any(|e| e == name));
+                if excluded {
+                    continue;
+                }
+
+                let name = relative
+                    .components()
+                    .next()
+                    .map(|component| component.as_os_str().to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let owner = match input.source_of(path) {
+                    Some(source) if source.excludes.is_some() => {
+                        format!("excludes of source {}", source.path.display())
+                    }
+                    _ => "input.excludes".to_string(),
+                };
+                issues.push(Issue::Error(if name.is_empty() {
+                    format!(
+                        "output path {} is the same as input path",
+                        library.display()
+                    )
+                } else {
+                    format!(
+                        "output path {} is inside input path {}, add \"{name}\" to {owner}",
+                        library.display(),
+                        path.display()
+                    )
+                }));
+            }
+        }
+
+        let lists = iter::once(&input.exts).chain(
+            input
+                .sources
+                .iter()
+                .filter_map(|source| source.exts.as_ref()),
+        );
+        for list in lists {
+            let mut exts = HashSet::new();
+            for ext in list.iter() {
+                if ext.starts_with('.') {
+                    issues.push(Issue::Error(format!(
+                        "ext \"{ext}\" starts with a dot, use \"{}\"",
+                        ext.trim_start_matches('.')
+                    )));
+                }
+                if !exts.insert(ext.to_lowercase()) {
+                    issues.push(Issue::Warning(format!("ext \"{ext}\" is duplicated")));
+                }
+            }
+        }
+
+        for hook in self.notify.iter() {
+            if hook.method == Method::Get && hook.body.is_some() {
+                issues.push(Issue::Warning(format!(
+                    "body of notify {} is not sent with GET",
+                    hook.url
+                )));
+            }
+        }
+
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Source;
+    use pretty_assertions::assert_eq;
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+
+    fn config(input: &Path, output: &Path) -> Config {
+        let mut config = toml::from_str::<Config>(Config::DEFAULT_CONFIG).unwrap();
+        config.input.path = Some(input.to_path_buf());
+        config.output.path = output.to_path_buf();
+        config
+    }
+
+    #[test]
+    fn test_lint() {
+        let root = env::temp_dir().join(format!("javcap-lint-{}", std::process::id()));
+        let input = root.join("input");
+        fs::create_dir_all(input.join("output")).unwrap();
+
+        let cases = [
+            (config(&input, &input.join("output")), vec![]),
+            (
+                config(&input, &input.join("library")),
+                vec![
+                    Issue::Warning(format!(
+                        "output path {} does not exist, it will be created",
+                        input.join("library").display()
+                    )),
+                    Issue::Error(format!(
+                        "output path {} is inside input path {}, add \"library\" to input.excludes",
+                        input.join("library").display(),
+                        input.display()
+                    )),
+                ],
+            ),
+            (
+                config(&input, &input),
+                vec![Issue::Error(format!(
+                    "output path {} is the same as input path",
+                    input.display()
+                ))],
+            ),
+            (
+                config(&root.join("missing"), &input.join("output")),
+                vec![Issue::Error(format!(
+                    "input path {} does not exist",
+                    root.join("missing").display()
+                ))],
+            ),
+            (
+                {
+                    let mut config = config(&input, &input.join("output"));
+                    config.input.sources.push(Source {
+                        path: input.join("a"),
+                        exts: None,
+                        excludes: Some(Vec::new()),
+                        output: Some(input.join("a").join("output")),
+                        rule: None,
+                    });
+                    config
+                },
+                vec![
+                    Issue::Error(format!(
+                        "input path {} does not exist",
+                        input.join("a").display()
+                    )),
+                    Issue::Warning(format!(
+                        "output path {} does not exist, it will be created",
+                        input.join("a").join("output").display()
+                    )),
+                    Issue::Error(format!(
+                        "output path {} is inside input path {}, add \"output\" to excludes of source {}",
+                        input.join("a").join("output").display(),
+                        input.join("a").display(),
+                        input.join("a").display()
+                    )),
+                ],
+            ),
+            (
+                {
+                    let mut config = config(&input, &input.join("output"));
+                    config.input.exts =
+                        vec!["mp4".to_string(), ".mkv".to_string(), "MP4".to_string()];
+                    config
+                },
+                vec![
+                    Issue::Error("ext \".mkv\" starts with a dot, use \"mkv\"".to_string()),
+                    Issue::Warning("ext \"MP4\" is duplicated".to_string()),
+                ],
+            ),
+            (
+                {
+                    let mut config = config(&input, &input.join("output"));
+                    config.notify = toml::from_str::<Config>(&format!(
+                        "{}\n{}",
+                        Config::DEFAULT_CONFIG,
+                        "[[notify]]\nurl = \"http://127.0.0.1:8080/hook\"\nmethod = \"GET\"\nbody = \"{}\"\n"
+                    ))
+                    .unwrap()
+                    .notify;
+                    config
+                },
+                vec![Issue::Warning(
+                    "body of notify http://127.0.0.1:8080/hook is not sent with GET".to_string(),
+                )],
+            ),
+        ];
+        for (config, expected) in cases {
+            assert_eq!(config.lint(), expected);
+        }
+
+        fs::remove_dir_all(root).ok();
+    }
+}
@@ -0,0 +1,52 @@
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct Logging {
+    #[serde(default = "Logging::default_keep")]
+    #[validate(range(min = 1, message = "should be larger than 0"))]
+    pub keep: usize,
+
+    #[serde(default)]
+    pub format: LogFormat,
+}
+
+impl Logging {
+    fn default_keep() -> usize {
+        10
+    }
+}
+
+impl Default for Logging {
+    fn default() -> Logging {
+        Logging {
+            keep: Logging::default_keep(),
+            format: LogFormat::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, Clone, Copy)]
+pub enum LogFormat {
+    #[default]
+    #[serde(rename = "text")]
+    Text,
+
+    #[serde(rename = "json")]
+    Json,
+}
+
+impl Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                LogFormat::Text => "text",
+                LogFormat::Json => "json",
+            }
+        )
+    }
+}
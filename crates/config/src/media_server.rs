@@ -0,0 +1,28 @@
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum MediaServer {
+    #[serde(rename = "jellyfin")]
+    Jellyfin { url: String, key: String },
+    #[serde(rename = "emby")]
+    Emby { url: String, key: String },
+    #[serde(rename = "kodi")]
+    Kodi {
+        url: String,
+        user: Option<String>,
+        password: Option<String>,
+    },
+}
+
+impl Display for MediaServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MediaServer::Jellyfin { url, .. } => write!(f, "jellyfin {url}"),
+            MediaServer::Emby { url, .. } => write!(f, "emby {url}"),
+            MediaServer::Kodi { url, .. } => write!(f, "kodi {url}"),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// a webhook called when something happens
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct Notify {
+    #[validate(url(message = "should be a url"))]
+    pub url: String,
+
+    #[serde(default)]
+    pub method: Method,
+
+    /// json with `{{key}}` placeholders, the whole event is sent if not set,
+    /// never sent with GET, which has the fields in the query string instead
+    pub body: Option<String>,
+
+    #[serde(default = "Notify::default_on")]
+    pub on: Vec<NotifyOn>,
+}
+
+impl Notify {
+    fn default_on() -> Vec<NotifyOn> {
+        vec![NotifyOn::Run]
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, Clone, Copy)]
+pub enum Method {
+    #[serde(rename = "GET")]
+    Get,
+
+    #[default]
+    #[serde(rename = "POST")]
+    Post,
+
+    #[serde(rename = "PUT")]
+    Put,
+}
+
+impl Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Method::Get => "GET",
+                Method::Post => "POST",
+                Method::Put => "PUT",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Copy)]
+pub enum NotifyOn {
+    #[serde(rename = "run")]
+    Run,
+
+    #[serde(rename = "succeed")]
+    Succeed,
+
+    #[serde(rename = "failed")]
+    Failed,
+}
+
+impl Display for NotifyOn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                NotifyOn::Run => "run",
+                NotifyOn::Succeed => "succeed",
+                NotifyOn::Failed => "failed",
+            }
+        )
+    }
+}
@@ -1,3 +1,4 @@
+use std::fmt::{self, Display};
 use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
@@ -5,16 +6,87 @@ use validator::Validate;
 
 use super::helper::absolute_path;
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
 pub struct Output {
     #[validate(custom(function = "absolute_path"))]
     pub path: PathBuf,
 
     #[validate(length(min = 1, message = "should have at least 1 rule"))]
     pub rule: Vec<Tag>,
+
+    #[serde(default)]
+    pub mode: Mode,
+
+    #[serde(default)]
+    pub on_conflict: Conflict,
+
+    #[validate(custom(function = "absolute_path"))]
+    pub failed_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, Clone, Copy)]
+pub enum Conflict {
+    #[default]
+    #[serde(rename = "skip")]
+    Skip,
+
+    #[serde(rename = "overwrite")]
+    Overwrite,
+
+    #[serde(rename = "rename")]
+    Rename,
+
+    #[serde(rename = "keep-larger")]
+    KeepLarger,
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Conflict::Skip => "skip",
+                Conflict::Overwrite => "overwrite",
+                Conflict::Rename => "rename",
+                Conflict::KeepLarger => "keep-larger",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, Clone, Copy)]
+pub enum Mode {
+    #[default]
+    #[serde(rename = "move")]
+    Move,
+
+    #[serde(rename = "copy")]
+    Copy,
+
+    #[serde(rename = "hardlink")]
+    Hardlink,
+
+    #[serde(rename = "symlink")]
+    Symlink,
+}
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Mode::Move => "move",
+                Mode::Copy => "copy",
+                Mode::Hardlink => "hardlink",
+                Mode::Symlink => "symlink",
+            }
+        )
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
 pub enum Tag {
     #[serde(rename = "title")]
     Title,
@@ -0,0 +1,259 @@
+use std::env;
+use std::str::FromStr;
+
+use anyhow::{Context, Result, bail};
+use toml::{Table, Value};
+
+use super::Config;
+
+/// a `key=value` pair replacing a field of the loaded config, key is dotted like `network.proxy`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Override {
+    pub key: String,
+    pub value: String,
+}
+
+impl FromStr for Override {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((key, value)) = s.split_once('=') else {
+            return Err(format!("{s} should be like key=value"));
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(format!("key of {s} is empty"));
+        }
+
+        Ok(Override {
+            key: key.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+impl Override {
+    const ENV_PREFIX: &str = "JAVCAP_";
+
+    /// overrides from env vars like `JAVCAP_NETWORK__PROXY`, `__` separates the levels
+    pub fn from_env() -> Vec<Override> {
+        Self::from_vars(env::vars())
+    }
+
+    fn from_vars(vars: impl Iterator<Item = (String, String)>) -> Vec<Override> {
+        let mut overrides = vars
+            .filter_map(|(name, value)| {
+                let key = name.strip_prefix(Self::ENV_PREFIX)?;
+                let key = key
+                    .split("__")
+                    .map(|part| part.to_lowercase())
+                    .collect::<Vec<_>>()
+                    .join(".");
+                Some(Override { key, value })
+            })
+            .collect::<Vec<_>>();
+        // env vars come in no particular order
+        overrides.sort_by(|a, b| a.key.cmp(&b.key));
+
+        overrides
+    }
+
+    /// value is read as toml if `typed`, else kept as a string
+    fn apply_to(&self, root: &mut Value, typed: bool) -> Result<()> {
+        let keys = self.key.split('.').collect::<Vec<_>>();
+        let Some((last, parents)) = keys.split_last() else {
+            bail!("empty key");
+        };
+        let Some(parent) = lookup(root, parents) else {
+            bail!("{} not found", parents.join("."));
+        };
+
+        match parent {
+            Value::Table(table) => {
+                let value = parse(&self.value, table.get(*last), typed);
+                table.insert(last.to_string(), value);
+            }
+            Value::Array(array) => {
+                let Some(slot) = last
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|idx| array.get_mut(idx))
+                else {
+                    bail!("{} not found", self.key);
+                };
+                *slot = parse(&self.value, Some(slot), typed);
+            }
+            _ => bail!("{} is not a table", parents.join(".")),
+        }
+
+        Ok(())
+    }
+}
+
+/// strings are kept as they are, others are read as toml, like `3`, `true` or `["mp4", "mkv"]`
+fn parse(raw: &str, existing: Option<&Value>, typed: bool) -> Value {
+    if !typed || matches!(existing, Some(Value::String(_))) {
+        return Value::String(raw.to_string());
+    }
+
+    toml::from_str::<Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+fn lookup<'a>(mut value: &'a mut Value, keys: &[&str]) -> Option<&'a mut Value> {
+    for key in keys {
+        value = match value {
+            Value::Table(table) => table.get_mut(*key)?,
+            Value::Array(array) => array.get_mut(key.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+
+    Some(value)
+}
+
+impl Config {
+    /// replace fields with the overrides in order, so later ones win
+    pub fn apply(self, overrides: &[Override]) -> Result<Config> {
+        if overrides.is_empty() {
+            return Ok(self);
+        }
+
+        let mut value = Value::try_from(&self).with_context(|| "encode config")?;
+        for item in overrides {
+            let mut typed = value.clone();
+            item.apply_to(&mut typed, true)
+                .with_context(|| format!("override {}", item.key))?;
+            // a field not set has no value to take the type from, so a value like
+            // `123456` is kept as a string if the config can only be decoded that way
+            if typed.clone().try_into::<Config>().is_err() {
+                let mut text = value.clone();
+                item.apply_to(&mut text, false)
+                    .with_context(|| format!("override {}", item.key))?;
+                if text.clone().try_into::<Config>().is_ok() {
+                    value = text;
+                    continue;
+                }
+            }
+            value = typed;
+        }
+        let config = value
+            .try_into::<Config>()
+            .with_context(|| "decode overridden config")?;
+
+        // unknown keys are dropped when decoding, so they can only be found after it
+        let mut value = Value::try_from(&config).with_context(|| "encode config")?;
+        for item in overrides {
+            let keys = item.key.split('.').collect::<Vec<_>>();
+            if lookup(&mut value, &keys).is_none() {
+                bail!("override {}: unknown key", item.key);
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Translator;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn config() -> Config {
+        let content = Config::DEFAULT_CONFIG.replace("path = \"\"", "path = \"/data\"");
+        toml::from_str::<Config>(&format!(
+            "{content}\n[[translators]]\ntype = \"youdao\"\nkey = \"\"\nsecret = \"\"\n"
+        ))
+        .unwrap()
+    }
+
+    fn set(s: &str) -> Override {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(
+            "network.proxy=http://a=b".parse::<Override>(),
+            Ok(Override {
+                key: "network.proxy".to_string(),
+                value: "http://a=b".to_string(),
+            })
+        );
+        assert!("network.proxy".parse::<Override>().is_err());
+        assert!("=1".parse::<Override>().is_err());
+    }
+
+    #[test]
+    fn test_from_vars() {
+        let vars = [
+            ("JAVCAP_TASK_LIMIT", "5"),
+            ("JAVCAP_NETWORK__PROXY", "http://127.0.0.1:7890"),
+            ("PATH", "/bin"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        assert_eq!(
+            Override::from_vars(vars.into_iter()),
+            vec![
+                set("network.proxy=http://127.0.0.1:7890"),
+                set("task_limit=5"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply() -> Result<()> {
+        let config = config().apply(&[
+            set("task_limit=5"),
+            set("network.proxy=http://127.0.0.1:7890"),
+            set("input.exts=[\"mp4\", \"mkv\"]"),
+            set("output.path=/library"),
+            set("translators.0.key=123"),
+            set("task_limit=6"),
+        ])?;
+        assert_eq!(config.task_limit, 6);
+        assert_eq!(
+            config.network.proxy.as_deref(),
+            Some("http://127.0.0.1:7890")
+        );
+        assert_eq!(config.input.exts, vec!["mp4", "mkv"]);
+        assert_eq!(config.output.path, PathBuf::from("/library"));
+        assert!(matches!(
+            config.translators.as_deref(),
+            Some([Translator::Youdao { key, .. }]) if key == "123"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_untyped() -> Result<()> {
+        // key and proxy are not set, there is no value to take the type from
+        let config = config().apply(&[
+            set("the_porn_db.key=123456"),
+            set("network.proxy=true"),
+            set("task_limit=4"),
+        ])?;
+        assert_eq!(config.the_porn_db.key.as_deref(), Some("123456"));
+        assert_eq!(config.network.proxy.as_deref(), Some("true"));
+        assert_eq!(config.task_limit, 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_invalid() {
+        for item in [
+            "netwrk.proxy=http://127.0.0.1:7890",
+            "network.proxyy=http://127.0.0.1:7890",
+            "task_limit=many",
+            "translators.1.key=123",
+        ] {
+            assert!(config().apply(&[set(item)]).is_err(), "{item}");
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// a finder run as an external command
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct Plugin {
+    #[validate(length(min = 1, message = "empty"))]
+    pub name: String,
+
+    /// program and its args
+    #[validate(length(min = 1, message = "empty"))]
+    pub command: Vec<String>,
+
+    /// seconds to wait for the command, network timeout is used if not set
+    #[validate(range(min = 1, message = "should be larger than 0"))]
+    pub timeout: Option<u64>,
+}
@@ -0,0 +1,86 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tokio::fs;
+
+/// per video overrides, loaded from `<video>.javcap.toml` next to the video
+#[derive(Debug, Default, Deserialize, PartialEq)]
+pub struct Sidecar {
+    /// used instead of the id parsed from file name
+    pub id: Option<String>,
+
+    /// only search in these finders
+    pub finders: Option<Vec<String>>,
+
+    #[serde(default)]
+    pub nfo: NfoOverride,
+}
+
+/// fields which win over the merged nfo
+#[derive(Debug, Default, Deserialize, PartialEq)]
+pub struct NfoOverride {
+    pub title: Option<String>,
+    pub plot: Option<String>,
+    pub rating: Option<f64>,
+    pub runtime: Option<u32>,
+    pub director: Option<String>,
+    pub premiered: Option<String>,
+    pub studio: Option<String>,
+    pub genres: Option<Vec<String>>,
+    pub actors: Option<Vec<String>>,
+}
+
+impl Sidecar {
+    pub const EXT: &str = "javcap.toml";
+
+    pub async fn load(path: impl AsRef<Path>) -> Result<Sidecar> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .await
+            .with_context(|| format!("read {}", path.display()))?;
+        let sidecar = toml::from_str::<Sidecar>(&content)
+            .with_context(|| format!("decode {}", path.display()))?;
+
+        Ok(sidecar)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_decode() -> Result<()> {
+        let content = r#"
+            id = "ABP-123"
+            finders = ["javdb"]
+
+            [nfo]
+            title = "title"
+            actors = ["actor"]
+        "#;
+        let actual = toml::from_str::<Sidecar>(content)?;
+        let expected = Sidecar {
+            id: Some("ABP-123".to_string()),
+            finders: Some(vec!["javdb".to_string()]),
+            nfo: NfoOverride {
+                title: Some("title".to_string()),
+                actors: Some(vec!["actor".to_string()]),
+                ..Default::default()
+            },
+        };
+        assert_eq!(actual, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_empty() -> Result<()> {
+        let actual = toml::from_str::<Sidecar>("")?;
+        assert_eq!(actual, Sidecar::default());
+
+        Ok(())
+    }
+}
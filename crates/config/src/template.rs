@@ -0,0 +1,118 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use toml::Value;
+use validator::Validate;
+
+use super::{Config, Translator};
+
+#[derive(Serialize)]
+struct Translators<'a> {
+    translators: &'a [Translator],
+}
+
+impl Config {
+    /// default config with given values filled in, comments are kept
+    pub fn generate(
+        input: &Path,
+        output: &Path,
+        translators: &[Translator],
+        proxy: Option<&str>,
+    ) -> Result<String> {
+        let quote = |value: &str| Value::String(value.to_string()).to_string();
+        let translators = if translators.is_empty() {
+            String::new()
+        } else {
+            toml::to_string(&Translators { translators }).with_context(|| "encode translators")?
+        };
+
+        // output inside input has to be excluded, or videos in it are found again
+        let exclude = output
+            .strip_prefix(input)
+            .ok()
+            .and_then(|relative| relative.components().next())
+            .map(|component| component.as_os_str().to_string_lossy().to_string());
+
+        let mut content = String::new();
+        let mut section = "";
+        for line in Self::DEFAULT_CONFIG.lines() {
+            if line.starts_with('[') {
+                section = line;
+                if section == "[input]" && !translators.is_empty() {
+                    content.push_str(&translators);
+                    content.push('\n');
+                }
+            }
+
+            let line = match (section, line) {
+                ("[input]", "path = \"\"") => format!("path = {}", quote(&input.to_string_lossy())),
+                ("[output]", "path = \"\"") => {
+                    format!("path = {}", quote(&output.to_string_lossy()))
+                }
+                ("[input]", "excludes = [\"output\"]") => match exclude {
+                    Some(ref exclude) => format!("excludes = [{}]", quote(exclude)),
+                    None => line.to_string(),
+                },
+                ("[network]", "# proxy = \"\"") => match proxy {
+                    Some(proxy) => format!("proxy = {}", quote(proxy)),
+                    None => line.to_string(),
+                },
+                _ => line.to_string(),
+            };
+            content.push_str(&line);
+            content.push('\n');
+        }
+
+        let config =
+            toml::from_str::<Config>(&content).with_context(|| "decode generated config")?;
+        config
+            .validate()
+            .with_context(|| "validate generated config")?;
+
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_generate() -> Result<()> {
+        let translators = [
+            Translator::DeepL,
+            Translator::Youdao {
+                key: "key".to_string(),
+                secret: "secret".to_string(),
+            },
+        ];
+        let content = Config::generate(
+            Path::new("/data/input"),
+            Path::new("/data/input/library"),
+            &translators,
+            Some("http://127.0.0.1:7890"),
+        )?;
+        let config = toml::from_str::<Config>(&content)?;
+        assert_eq!(config.input.path, Some(PathBuf::from("/data/input")));
+        assert_eq!(config.output.path, PathBuf::from("/data/input/library"));
+        assert_eq!(config.input.excludes, vec!["library".to_string()]);
+        assert_eq!(
+            config.network.proxy.as_deref(),
+            Some("http://127.0.0.1:7890")
+        );
+        assert_eq!(config.translators.map(|t| t.len()), Some(2));
+        // comments of the default config are kept
+        assert!(content.contains("# 输入路径, 必须是绝对路径"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_relative_path() {
+        let res = Config::generate(Path::new("input"), Path::new("/output"), &[], None);
+        assert!(res.is_err());
+    }
+}
@@ -1,91 +1,383 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io;
+use std::mem;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::{Context, Result, bail};
+use bon::bon;
 use colored::Colorize;
-use config::Config;
+use config::{Config, Conflict, Mode, NfoOverride, Sidecar};
 use log::{error, info, warn};
-use tokio::fs;
+use nfo::Nfo;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use spider::Picker;
 use tokio::sync::mpsc::error::SendError;
-use tokio::sync::mpsc::{self, Receiver};
+use tokio::sync::mpsc::{self, Sender};
+use tokio::sync::watch::Receiver;
 use tokio::task::JoinSet;
+use tokio::{fs, time};
 use validator::Validate;
 use video::{Video, VideoFile, VideoType};
 
 use super::bar::Bar;
-use super::helper::Helper;
+use super::failure::Failure;
+use super::helper::{Helper, cache_dir};
+use super::journal::JournalKind;
+use super::ledger::{self, Fingerprint, Ledger, Outcome};
+use super::logs::with_video_id;
 use super::message::Message;
-use super::payload::Payload;
+use super::payload::{Artifact, Payload};
+use super::picker::Prompt;
+use super::report::{FinderError, Record, Report, Trace};
+use super::shutdown;
+use super::webhook::Notice;
+
+struct Pending {
+    size: Option<u64>,
+    modified: Option<SystemTime>,
+    since: Instant,
+}
+
+impl Default for Pending {
+    fn default() -> Pending {
+        Pending {
+            size: None,
+            modified: None,
+            since: Instant::now(),
+        }
+    }
+}
 
 pub struct App {
     config: Config,
     videos: HashMap<VideoType, Video>,
+    sidecars: HashMap<VideoType, Sidecar>,
     tasks: JoinSet<std::result::Result<(), SendError<Message>>>,
     succeed: Vec<String>,
-    failed: Vec<String>,
+    failed: Vec<Failure>,
+    /// videos left in input as the output already has them
+    kept: Vec<String>,
     helper: Arc<Helper>,
     bar: Arc<Bar>,
+    dry_run: bool,
+    force: bool,
+    rescrape: Option<Vec<Artifact>>,
+    ledger: Ledger,
+    fingerprints: HashMap<PathBuf, Fingerprint>,
+    known: usize,
+    report: Option<Report>,
+    stop: Receiver<Option<time::Instant>>,
+    stopping: bool,
+    skipped: Vec<String>,
+    /// videos sent to tasks and not handled yet, interrupted if out of time
+    unfinished: HashMap<String, Video>,
+    /// dirs with new videos, for media servers to refresh
+    changed: BTreeSet<PathBuf>,
 }
 
+#[bon]
 impl App {
-    pub async fn new(config: Config) -> Result<App> {
-        let helper = Helper::new(&config).with_context(|| "build helper")?;
-        let bar = Bar::new().await;
+    #[builder]
+    pub async fn new(
+        config: Config,
+        #[builder(default)] dry_run: bool,
+        #[builder(default)] interactive: bool,
+        #[builder(default)] force: bool,
+        #[builder(default)] exclude_finders: Vec<String>,
+        #[builder(default)] journal: JournalKind,
+        report: Option<PathBuf>,
+        stop: Option<Receiver<Option<time::Instant>>>,
+    ) -> Result<App> {
+        let bar = Arc::new(Bar::new().await);
+        let picker = if interactive {
+            Some(Arc::new(Prompt::new(bar.clone())) as Arc<dyn Picker>)
+        } else {
+            None
+        };
+        let mut helper = Helper::new(&config, picker, journal).with_context(|| "build helper")?;
+        helper.spider = helper
+            .spider
+            .except(&exclude_finders)
+            .with_context(|| "exclude finders")?;
+        let ledger = Ledger::load(cache_dir().join("ledger"))
+            .await
+            .with_context(|| "load ledger")?;
+        let stop = stop.unwrap_or_else(|| shutdown::listen(config.grace()));
         let app = App {
             tasks: JoinSet::new(),
             config,
             succeed: Vec::new(),
             failed: Vec::new(),
+            kept: Vec::new(),
             videos: HashMap::new(),
+            sidecars: HashMap::new(),
             helper: Arc::new(helper),
-            bar: Arc::new(bar),
+            bar,
+            dry_run,
+            force,
+            rescrape: None,
+            ledger,
+            fingerprints: HashMap::new(),
+            known: 0,
+            report: report.map(Report::new),
+            stop,
+            stopping: false,
+            skipped: Vec::new(),
+            unfinished: HashMap::new(),
+            changed: BTreeSet::new(),
         };
 
         Ok(app)
     }
 
-    async fn start_all_tasks(&mut self) -> Result<Receiver<Message>> {
-        let (tx, rx) = mpsc::channel(10);
-        for video in self.videos.clone().into_values() {
+    /// progress of the videos found, shared with whoever wants to show it
+    pub fn bar(&self) -> Arc<Bar> {
+        self.bar.clone()
+    }
+
+    async fn start_all_tasks(&mut self, tx: &Sender<Message>) {
+        let videos = mem::take(&mut self.videos);
+        self.bar.add_total(videos.len()).await;
+        for video in videos.into_values() {
+            self.unfinished
+                .insert(video.ty().to_string(), video.clone());
             let tx = tx.clone();
             let helper = self.helper.clone();
             let bar = self.bar.clone();
+            let sidecar = self.sidecars.remove(video.ty());
             self.tasks.spawn(async move {
-                let name = video.ty().to_string();
-                info!("add {name} to queue");
-                let msg = match Self::process_video(video, helper, bar).await {
-                    Ok(payload) => Message::Loaded(Box::new(payload)),
-                    Err(e) => Message::Failed(name, format!("{e:?}")),
+                info!("add {} to queue", video.ty());
+                let mut trace = Trace::new();
+                // closed when stopping, videos not started yet are skipped
+                let Ok(_permit) = helper.sema.acquire().await else {
+                    return tx.send(Message::Skipped(Box::new(video), trace)).await;
+                };
+                let task =
+                    Self::process_video(video.clone(), sidecar, helper.clone(), bar, &mut trace);
+                let msg = match with_video_id(video.ty().to_string(), task).await {
+                    Ok(payload) => Message::Loaded(Box::new(payload), trace),
+                    Err(e) => Message::Failed(Box::new(video), format!("{e:?}"), trace),
                 };
                 tx.send(msg).await
             });
         }
-
-        Ok(rx)
     }
 
     pub async fn run(mut self) -> Result<()> {
         self.load_all_videos()
             .await
             .with_context(|| "load videos")?;
-        let mut rx = self
-            .start_all_tasks()
+
+        self.process_all_videos().await
+    }
+
+    /// refresh artifacts of videos already in output dir, videos are not moved
+    pub async fn rescrape(mut self, artifacts: Vec<Artifact>) -> Result<()> {
+        self.load_library_videos()
             .await
-            .with_context(|| "start all tasks")?;
+            .with_context(|| "load library videos")?;
+        self.rescrape = Some(artifacts);
+
+        self.process_all_videos().await
+    }
 
-        while let Some(msg) = rx.recv().await {
+    /// process only the videos in path, which is a video file or a dir of them
+    pub async fn scrape(mut self, path: &Path) -> Result<()> {
+        if path.is_dir() {
+            for file in Self::walk_dir(path, self.config.input.excludes_of(path))
+                .await
+                .with_context(|| "walk dir")?
+            {
+                self.try_add_video_file(&file).await;
+            }
+        } else if path.is_file() {
+            self.try_add_video_file(path).await;
+        } else {
+            bail!("{} not found", path.display());
+        }
+
+        self.process_all_videos().await
+    }
+
+    /// process only the videos failed in the last run
+    pub async fn retry(mut self, failures: Vec<Failure>) -> Result<()> {
+        self.force = true;
+        for file in failures.iter().flat_map(|failure| failure.files.iter()) {
+            if !file.exists() {
+                warn!("skip {}, not found", file.display());
+                self.bar
+                    .message(format!("skip {}, not found", file.display()));
+                continue;
+            }
+            self.try_add_video_file(file).await;
+        }
+
+        self.process_all_videos().await
+    }
+
+    async fn process_all_videos(&mut self) -> Result<()> {
+        let (tx, mut rx) = mpsc::channel(10);
+        self.start_all_tasks(&tx).await;
+        drop(tx);
+
+        while let Some(msg) = self.next_message(&mut rx).await {
             self.handle_message(msg).await;
         }
 
         self.wait_for_all_tasks()
             .await
             .with_context(|| "wait for all tasks")?;
+        self.helper.journal.commit().await;
+        self.ledger.save().await.with_context(|| "save ledger")?;
         self.summary().await;
+        self.refresh_media_servers().await;
+        self.save_report().await.with_context(|| "save report")?;
+        self.save_failures()
+            .await
+            .with_context(|| "save failures")?;
 
         Ok(())
     }
 
+    pub async fn watch(mut self, settle: Duration) -> Result<()> {
+        self.load_all_videos()
+            .await
+            .with_context(|| "load videos")?;
+        let (tx, mut rx) = mpsc::channel(10);
+        self.start_all_tasks(&tx).await;
+
+        let (event_tx, mut event_rx) = mpsc::channel(100);
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+                Ok(event) => {
+                    event_tx.blocking_send(event).ok();
+                }
+                Err(e) => error!("watch error, caused by {e}"),
+            })
+            .with_context(|| "build watcher")?;
+        let inputs = self
+            .config
+            .input
+            .paths()
+            .into_iter()
+            .map(Path::to_path_buf)
+            .collect::<Vec<_>>();
+        for input in inputs.iter() {
+            watcher
+                .watch(input, RecursiveMode::Recursive)
+                .with_context(|| format!("watch {}", input.display()))?;
+            info!("watching {}", input.display());
+            self.bar.message(format!("watching {}", input.display()));
+        }
+
+        let mut pending = HashMap::new();
+        let mut interval = time::interval(Duration::from_secs(1));
+        loop {
+            tokio::select! {
+                Some(msg) = rx.recv() => self.handle_message(msg).await,
+                Some(event) = event_rx.recv() => self.handle_event(event, &mut pending),
+                _ = interval.tick() => {
+                    let settled = Self::take_settled(&mut pending, settle).await;
+                    if !settled.is_empty() {
+                        for file in settled {
+                            self.try_add_video_file(&file).await;
+                        }
+                        self.start_all_tasks(&tx).await;
+                    }
+                    while let Some(task) = self.tasks.try_join_next() {
+                        task??;
+                    }
+                    if self.tasks.is_empty() {
+                        self.helper.journal.commit().await;
+                        if let Err(e) = self.ledger.save().await {
+                            warn!("could not save ledger, caused by {e:?}");
+                        }
+                        self.refresh_media_servers().await;
+                    }
+                }
+                _ = shutdown::stopped(self.stop.clone()) => break,
+            }
+        }
+
+        for input in inputs.iter() {
+            info!("stop watching {}", input.display());
+        }
+        drop(watcher);
+        drop(tx);
+        while let Some(msg) = self.next_message(&mut rx).await {
+            self.handle_message(msg).await;
+        }
+
+        self.wait_for_all_tasks()
+            .await
+            .with_context(|| "wait for all tasks")?;
+        self.helper.journal.commit().await;
+        self.ledger.save().await.with_context(|| "save ledger")?;
+        self.summary().await;
+        self.refresh_media_servers().await;
+        self.save_report().await.with_context(|| "save report")?;
+        self.save_failures()
+            .await
+            .with_context(|| "save failures")?;
+
+        Ok(())
+    }
+
+    async fn refresh_media_servers(&mut self) {
+        let dirs = mem::take(&mut self.changed).into_iter().collect::<Vec<_>>();
+        self.helper.refresher.refresh(&dirs).await;
+    }
+
+    fn handle_event(&self, event: Event, pending: &mut HashMap<PathBuf, Pending>) {
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            return;
+        }
+
+        for file in event.paths {
+            if !file.is_file() || self.config.input.is_excluded(&file) {
+                continue;
+            }
+
+            info!("file changed {}", file.display());
+            pending.insert(file, Pending::default());
+        }
+    }
+
+    async fn take_settled(
+        pending: &mut HashMap<PathBuf, Pending>,
+        settle: Duration,
+    ) -> Vec<PathBuf> {
+        let mut settled = Vec::new();
+        let files = pending.keys().cloned().collect::<Vec<_>>();
+        for file in files {
+            let Ok(meta) = fs::metadata(&file).await else {
+                pending.remove(&file);
+                continue;
+            };
+            let Some(state) = pending.get_mut(&file) else {
+                continue;
+            };
+
+            let size = meta.len();
+            let modified = meta.modified().ok();
+            if state.size != Some(size) || state.modified != modified {
+                state.size = Some(size);
+                state.modified = modified;
+                state.since = Instant::now();
+                continue;
+            }
+
+            if state.since.elapsed() >= settle {
+                pending.remove(&file);
+                settled.push(file);
+            }
+        }
+
+        settled
+    }
+
     fn print_bar(&self, msg: &Message) {
         let msg = format!(" {} ", msg);
         let len = msg.len();
@@ -102,80 +394,219 @@ impl App {
         ));
     }
 
-    async fn process_video(video: Video, helper: Arc<Helper>, bar: Arc<Bar>) -> Result<Payload> {
-        let _permit = helper
-            .sema
-            .acquire()
-            .await
-            .with_context(|| "acquire permit")?;
-
-        let mut nfo = helper
+    async fn process_video(
+        video: Video,
+        sidecar: Option<Sidecar>,
+        helper: Arc<Helper>,
+        bar: Arc<Bar>,
+        trace: &mut Trace,
+    ) -> Result<Payload> {
+        let finders = sidecar
+            .as_ref()
+            .and_then(|sidecar| sidecar.finders.clone())
+            .unwrap_or_default();
+        let started = Instant::now();
+        let found = helper
             .spider
-            .find(video.ty().clone())
+            .only(&finders)
+            .with_context(|| "filter finders")?
+            .search(video.ty().clone())
             .await
             .with_context(|| "find video")?;
+        trace.find_ms = started.elapsed().as_millis();
+        trace.finders_succeed = found.succeed;
+        trace.finders_failed = found
+            .failed
+            .into_iter()
+            .map(|(finder, error)| FinderError { finder, error })
+            .collect();
+        let Some(mut nfo) = found.nfo else {
+            bail!(
+                "could not find anything about {} in all finders",
+                video.ty()
+            );
+        };
+        trace.subtitle = !nfo.subtitle().is_empty();
         nfo.auto_fix_by_key(video.ty());
+        if let Some(ref sidecar) = sidecar {
+            Self::override_nfo(&mut nfo, &sidecar.nfo);
+        }
         info!("{nfo:?}");
         nfo.validate().with_context(|| "validate nfo")?;
         nfo.traditional_to_simplified();
 
-        let title_task = tokio::spawn({
-            let helper = helper.clone();
-            let title = nfo.title().clone();
-            async move {
-                helper
-                    .translator
-                    .translate(&title)
-                    .await
-                    .with_context(|| format!("translate {title}"))
-            }
-        });
-        let plot_task = tokio::spawn({
-            let helper = helper.clone();
-            let plot = nfo.plot().clone();
-            async move {
-                helper
-                    .translator
-                    .translate(&plot)
-                    .await
-                    .with_context(|| format!("translate {plot}"))
-            }
-        });
-
-        if let Some(title) = title_task.await?? {
-            info!("translated {title}");
-            nfo.set_title(title);
-        }
-        if let Some(plot) = plot_task.await?? {
-            info!("translated {plot}");
-            nfo.set_plot(plot);
+        let started = Instant::now();
+        trace.translated = Helper::translate(&helper, &mut nfo)
+            .await
+            .with_context(|| "translate nfo")?;
+        trace.translate_ms = started.elapsed().as_millis();
+        // values from sidecar should not be replaced by translation
+        if let Some(ref sidecar) = sidecar {
+            Self::override_nfo(&mut nfo, &sidecar.nfo);
         }
 
-        let payload = Payload::builder().video(video).nfo(nfo).bar(bar).build();
+        let payload = Payload::builder()
+            .video(video)
+            .nfo(nfo)
+            .bar(bar)
+            .journal(helper.journal.clone())
+            .build();
         Ok(payload)
     }
 
-    async fn handle_succeed(&mut self, payload: &Payload) -> Result<()> {
+    fn override_nfo(nfo: &mut Nfo, nfo_override: &NfoOverride) {
+        if let Some(ref title) = nfo_override.title {
+            nfo.set_title(title.clone());
+        }
+        if let Some(ref plot) = nfo_override.plot {
+            nfo.set_plot(plot.clone());
+        }
+        if let Some(rating) = nfo_override.rating {
+            nfo.set_rating(rating);
+        }
+        if let Some(runtime) = nfo_override.runtime {
+            nfo.set_runtime(runtime);
+        }
+        if let Some(ref director) = nfo_override.director {
+            nfo.set_director(director.clone());
+        }
+        if let Some(ref premiered) = nfo_override.premiered {
+            nfo.set_premiered(premiered.clone());
+        }
+        if let Some(ref studio) = nfo_override.studio {
+            nfo.set_studio(studio.clone());
+        }
+        if let Some(ref genres) = nfo_override.genres {
+            let old = nfo.genres_mut();
+            old.clear();
+            old.extend(genres.iter().cloned());
+        }
+        if let Some(ref actors) = nfo_override.actors {
+            let old = nfo.actors_mut();
+            old.clear();
+            old.extend(actors.iter().cloned());
+        }
+    }
+
+    /// return the dir where the payload goes, and whether the videos are moved or kept
+    async fn handle_succeed(&mut self, payload: &mut Payload) -> Result<(PathBuf, Outcome)> {
+        if self.dry_run {
+            let out = self.handle_dry_run(payload).await?;
+            return Ok((out, Outcome::Succeed));
+        }
+        if let Some(artifacts) = self.rescrape.clone() {
+            let out = self.handle_rescrape(payload, &artifacts).await?;
+            return Ok((out, Outcome::Succeed));
+        }
+
         let out = self.get_out_path(payload).await?;
         payload
-            .write_all_to(&out)
+            .rename_in(&out, self.config.output.on_conflict)
+            .await;
+        payload
+            .write_all_to(&out, self.config.output.on_conflict)
             .await
             .with_context(|| format!("write payload to {}", out.display()))?;
-        payload
-            .move_videos_to(&out)
+        let kept = payload
+            .move_videos_to(
+                &out,
+                self.config.output.mode,
+                self.config.output.on_conflict,
+            )
             .await
             .with_context(|| format!("move videos to {}", out.display()))?;
 
+        // kept files are not recorded, so that they are found again once the conflict is solved
+        for file in kept.iter() {
+            self.fingerprints.remove(file);
+        }
+        self.record(payload.video(), Outcome::Succeed, Some(out.clone()))
+            .await;
+        self.changed.insert(out.clone());
         self.bar.add().await;
         let ty = payload.video().ty();
-        info!("{ty} ok");
+        if kept.is_empty() {
+            if let Err(e) = self.release(payload.video()).await {
+                warn!("could not clean failed path, caused by {e:?}");
+            }
+            info!("{ty} ok");
+            self.succeed.push(ty.to_string());
+            Ok((out, Outcome::Succeed))
+        } else {
+            info!("{ty} kept in input, {} file(s) already exist", kept.len());
+            self.kept.push(ty.to_string());
+            Ok((out, Outcome::Kept))
+        }
+    }
+
+    async fn handle_dry_run(&mut self, payload: &mut Payload) -> Result<PathBuf> {
+        let out = self.concat_rule(payload);
+        self.bar
+            .message(format!("to {} {}", out.display(), "(dry run)".yellow()));
+        if out.is_file() {
+            bail!("target is a file");
+        }
+
+        if !out.exists() {
+            info!("plan to create dir {}", out.display());
+            self.bar.message(format!("create ... {}", out.display()));
+        }
+        payload
+            .rename_in(&out, self.config.output.on_conflict)
+            .await;
+        payload
+            .print_plan_to(
+                &out,
+                self.config.output.mode,
+                self.config.output.on_conflict,
+            )
+            .await
+            .with_context(|| format!("plan payload to {}", out.display()))?;
+
+        self.bar.add().await;
+        let ty = payload.video().ty();
+        info!("{ty} planned");
         self.succeed.push(ty.to_string());
-        Ok(())
+        Ok(out)
+    }
+
+    async fn handle_rescrape(
+        &mut self,
+        payload: &Payload,
+        artifacts: &[Artifact],
+    ) -> Result<PathBuf> {
+        let Some(out) = payload
+            .video()
+            .files()
+            .first()
+            .and_then(|file| file.location().parent())
+        else {
+            bail!("video has no parent dir");
+        };
+        self.bar.message(format!("in {}", out.display()));
+        payload
+            .write_some_to(out, artifacts, Conflict::Overwrite)
+            .await
+            .with_context(|| format!("write payload to {}", out.display()))?;
+
+        self.bar.add().await;
+        let ty = payload.video().ty();
+        info!("{ty} rescraped");
+        self.succeed.push(ty.to_string());
+        Ok(out.to_path_buf())
     }
 
     fn concat_rule(&self, payload: &Payload) -> PathBuf {
-        let mut out = self.config.output.path.to_path_buf();
-        for tag in self.config.output.rule.iter() {
+        // videos go where the source of their first file says
+        let file = payload
+            .video()
+            .files()
+            .first()
+            .map(|file| file.location().as_path())
+            .unwrap_or(Path::new(""));
+        let (out, rule) = self.config.output_of(file);
+        let mut out = out.to_path_buf();
+        for tag in rule.iter() {
             let name = payload.get_by_tag(tag);
             out = out.join(name);
         }
@@ -191,6 +622,18 @@ impl App {
         }
 
         if !out.exists() {
+            let created = out
+                .ancestors()
+                .take_while(|dir| !dir.exists())
+                .map(|dir| dir.to_path_buf())
+                .collect::<Vec<_>>();
+            for dir in created.iter().rev() {
+                self.helper
+                    .journal
+                    .create_dir(dir)
+                    .await
+                    .with_context(|| format!("record dir {}", dir.display()))?;
+            }
             fs::create_dir_all(&out)
                 .await
                 .with_context(|| format!("create dir for {}", out.display()))?;
@@ -199,33 +642,355 @@ impl App {
         Ok(out)
     }
 
-    async fn handle_failed(&mut self, name: String, err: String) {
+    async fn handle_failed(&mut self, video: &Video, err: String) {
         self.bar
             .message(format!("{}\n{}", "failed by".red(), err.red()));
 
-        self.bar.add().await;
+        let name = video.ty().to_string();
         error!("{name} failed, caused by {err}");
-        self.failed.push(name);
+        if let Err(e) = self.quarantine(video, &err).await {
+            error!("could not move {name} to failed path, caused by {e:?}");
+            self.bar.message(format!(
+                "{}\n{}",
+                "could not move to failed path".red(),
+                format!("{e:?}").red()
+            ));
+        }
+
+        self.record(video, Outcome::Failed, None).await;
+        self.bar.add().await;
+        let files = video
+            .files()
+            .iter()
+            .map(|file| self.locate(file.location()))
+            .collect();
+        if !self.dry_run {
+            let notice = Notice::Failed {
+                id: name.clone(),
+                error: err.clone(),
+            };
+            self.helper.notifier.send(notice).await;
+        }
+        self.failed.push(Failure {
+            id: name,
+            files,
+            error: err,
+        });
+    }
+
+    /// where the file is now, it may have been moved to failed path
+    fn locate(&self, file: &Path) -> PathBuf {
+        if !file.exists()
+            && let Some(ref failed_path) = self.config.output.failed_path
+            && let Some(name) = file.file_name()
+        {
+            let moved = failed_path.join(name);
+            if moved.exists() {
+                return moved;
+            }
+        }
+
+        file.to_path_buf()
     }
 
+    /// failures of this run, with those of earlier runs not handled in this run and still there
+    async fn save_failures(&self) -> Result<()> {
+        if self.dry_run || self.rescrape.is_some() {
+            return Ok(());
+        }
+
+        let handled = self
+            .succeed
+            .iter()
+            .chain(self.kept.iter())
+            .chain(self.failed.iter().map(|failure| &failure.id))
+            .collect::<HashSet<_>>();
+        let mut failures = Failure::load_all()
+            .await
+            .with_context(|| "load failures")?
+            .into_iter()
+            .filter(|failure| !handled.contains(&failure.id))
+            .filter(|failure| failure.files.iter().any(|file| file.exists()))
+            .collect::<Vec<_>>();
+        failures.extend(self.failed.iter().cloned());
+
+        Failure::save_all(&failures).await
+    }
+
+    async fn record(&mut self, video: &Video, outcome: Outcome, output: Option<PathBuf>) {
+        if self.dry_run || self.rescrape.is_some() {
+            return;
+        }
+
+        for file in video.files() {
+            let location = file.location();
+            let Some(fingerprint) = self.fingerprints.remove(location) else {
+                continue;
+            };
+            self.ledger.record(
+                location.clone(),
+                ledger::Record {
+                    fingerprint,
+                    name: video.ty().to_string(),
+                    outcome,
+                    output: output.clone(),
+                },
+            );
+        }
+    }
+
+    /// put files of a failed video to failed path by output mode, with the error beside them
+    async fn quarantine(&self, video: &Video, err: &str) -> Result<()> {
+        let Some(ref failed_path) = self.config.output.failed_path else {
+            return Ok(());
+        };
+        if self.dry_run || self.rescrape.is_some() {
+            return Ok(());
+        }
+
+        let journal = &self.helper.journal;
+        if !failed_path.exists() {
+            journal
+                .create_dir(failed_path)
+                .await
+                .with_context(|| format!("record dir {}", failed_path.display()))?;
+            fs::create_dir_all(failed_path)
+                .await
+                .with_context(|| format!("create dir {}", failed_path.display()))?;
+        }
+
+        let mode = self.config.output.mode;
+        let mut moved = false;
+        for file in video.files() {
+            let src = file.location();
+            // may have been moved before the failure, or is retried in failed path
+            if !src.exists() || src.starts_with(failed_path) {
+                continue;
+            }
+            let Some(name) = src.file_name() else {
+                continue;
+            };
+            let out = failed_path.join(name);
+            if out.exists() {
+                warn!("skip {}, already exists in failed path", src.display());
+                continue;
+            }
+
+            match Payload::place(journal, src, &out, mode).await {
+                // failed path may be on another filesystem, where a hard link can not be made
+                Err(e)
+                    if mode == Mode::Hardlink
+                        && e.root_cause()
+                            .downcast_ref::<io::Error>()
+                            .is_some_and(|e| e.kind() == io::ErrorKind::CrossesDevices) =>
+                {
+                    Payload::place(journal, src, &out, Mode::Copy).await?;
+                }
+                res => res?,
+            }
+            info!("{mode} {} to {}", src.display(), out.display());
+            moved = true;
+        }
+
+        let error_file = failed_path.join(format!("{}.error.txt", video.ty()));
+        if error_file.exists() {
+            journal.backup(&error_file).await?;
+        }
+        journal
+            .write_file(&error_file)
+            .await
+            .with_context(|| format!("record file {}", error_file.display()))?;
+        fs::write(&error_file, format!("{err}\n"))
+            .await
+            .with_context(|| format!("write to file {}", error_file.display()))?;
+        if moved {
+            self.bar.message(format!(
+                "{} {}",
+                format!("{mode} to").yellow(),
+                failed_path.display()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// remove what quarantine left in failed path for a video placed now, dropped once the run
+    /// commits
+    async fn release(&self, video: &Video) -> Result<()> {
+        let Some(ref failed_path) = self.config.output.failed_path else {
+            return Ok(());
+        };
+
+        let journal = &self.helper.journal;
+        // copies of the files if they were not moved, as the video is retried from input
+        let stale = video
+            .files()
+            .iter()
+            .filter(|file| !file.location().starts_with(failed_path))
+            .filter_map(|file| file.location().file_name())
+            .map(|name| failed_path.join(name))
+            .chain([failed_path.join(format!("{}.error.txt", video.ty()))]);
+        for file in stale {
+            if !file.is_file() {
+                continue;
+            }
+            journal.discard(&file).await?;
+            info!("remove stale {}", file.display());
+        }
+
+        Ok(())
+    }
+
+    /// next message to handle, `None` if all done or out of time after stop is asked
+    async fn next_message(&mut self, rx: &mut mpsc::Receiver<Message>) -> Option<Message> {
+        loop {
+            tokio::select! {
+                msg = rx.recv() => return msg,
+                _ = shutdown::stopped(self.stop.clone()), if !self.stopping => {
+                    self.stopping = true;
+                    self.helper.sema.close();
+                    let msg = format!(
+                        "interrupted, finishing videos in progress in {} seconds, press ctrl-c again to exit at once",
+                        self.config.grace
+                    );
+                    warn!("{msg}");
+                    self.bar.message(msg.yellow().to_string());
+                }
+                _ = shutdown::expired(self.stop.clone()), if self.stopping => {
+                    let msg = format!("out of time, abort {} unfinished task(s)", self.tasks.len());
+                    warn!("{msg}");
+                    self.bar.message(msg.yellow().to_string());
+                    self.tasks.abort_all();
+                    self.interrupt_unfinished(rx);
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// videos of queued messages and aborted tasks are never handled, count them as interrupted
+    fn interrupt_unfinished(&mut self, rx: &mut mpsc::Receiver<Message>) {
+        rx.close();
+        let mut traces = HashMap::new();
+        while let Ok(msg) = rx.try_recv() {
+            traces.insert(msg.to_string(), msg.trace().clone());
+        }
+
+        let err = "not finished in time".to_string();
+        for (name, video) in mem::take(&mut self.unfinished) {
+            warn!("{name} not finished in time");
+            let trace = traces.remove(&name).unwrap_or_else(Trace::new);
+            self.add_to_report(&video, trace, Outcome::Interrupted, None, Some(err.clone()));
+            self.skipped.push(name);
+        }
+    }
+
+    /// changes of the message are rolled back, if not finished before the deadline
     async fn handle_message(&mut self, msg: Message) {
+        let name = msg.to_string();
+        self.unfinished.remove(&name);
+        let video = msg.video().clone();
+        let trace = msg.trace().clone();
+        let mark = self.helper.journal.mark().await;
+        let stop = self.stop.clone();
+        let finished = tokio::select! {
+            _ = with_video_id(name.clone(), self.handle_message_of_video(msg)) => true,
+            _ = shutdown::expired(stop) => false,
+        };
+        if finished {
+            return;
+        }
+
+        let failed = self.helper.journal.rollback(mark).await;
+        if failed == 0 {
+            warn!("{name} not finished in time, changes rolled back");
+        } else {
+            error!("{name} not finished in time, {failed} change(s) could not be rolled back");
+        }
+        self.bar
+            .message(format!("{} {name}", "rolled back".yellow()));
+
+        // whatever was done is undone, so the video is found again by the next run
+        self.succeed.retain(|ty| *ty != name);
+        self.kept.retain(|ty| *ty != name);
+        self.failed.retain(|failure| failure.id != name);
+        for file in video.files() {
+            self.ledger.forget(file.location());
+        }
+        let err = "not finished in time, changes rolled back".to_string();
+        self.add_to_report(&video, trace, Outcome::Interrupted, None, Some(err));
+        self.skipped.push(name);
+    }
+
+    async fn handle_message_of_video(&mut self, msg: Message) {
+        if let Message::Skipped(video, trace) = msg {
+            info!("{} skipped, interrupted", video.ty());
+            self.bar.add().await;
+            self.skipped.push(video.ty().to_string());
+            self.add_to_report(&video, trace, Outcome::Interrupted, None, None);
+            return;
+        }
+
         self.print_bar(&msg);
         match msg {
-            Message::Loaded(payload) => {
-                if let Err(err) = self.handle_succeed(&payload).await {
-                    let ty = payload.video().ty();
-                    self.handle_failed(ty.to_string(), format!("{err:?}")).await;
+            Message::Loaded(mut payload, trace) => match self.handle_succeed(&mut payload).await {
+                Ok((out, outcome)) => {
+                    if !self.dry_run && outcome == Outcome::Succeed {
+                        let id = payload.video().ty().to_string();
+                        let notice = Notice::Succeed {
+                            id,
+                            output: out.clone(),
+                        };
+                        self.helper.notifier.send(notice).await;
+                    }
+                    self.add_to_report(payload.video(), trace, outcome, Some(out), None);
                 }
+                Err(err) => {
+                    let err = format!("{err:?}");
+                    self.handle_failed(payload.video(), err.clone()).await;
+                    self.add_to_report(payload.video(), trace, Outcome::Failed, None, Some(err));
+                }
+            },
+            Message::Failed(video, err, trace) => {
+                self.handle_failed(&video, err.clone()).await;
+                self.add_to_report(&video, trace, Outcome::Failed, None, Some(err));
             }
-            Message::Failed(name, err) => {
-                self.handle_failed(name, err).await;
-            }
+            Message::Skipped(..) => {}
         }
     }
 
+    fn add_to_report(
+        &mut self,
+        video: &Video,
+        trace: Trace,
+        outcome: Outcome,
+        output: Option<PathBuf>,
+        error: Option<String>,
+    ) {
+        if let Some(ref mut report) = self.report {
+            report.add(Record::new(video, trace, outcome, output, error));
+        }
+    }
+
+    async fn save_report(&self) -> Result<()> {
+        let Some(ref report) = self.report else {
+            return Ok(());
+        };
+        report.save().await?;
+        info!("report saved to {}", report.path().display());
+        println!("report saved to {}", report.path().display());
+
+        Ok(())
+    }
+
     async fn wait_for_all_tasks(&mut self) -> Result<()> {
         while let Some(task) = self.tasks.join_next().await {
-            task??;
+            match task {
+                Err(e) if e.is_cancelled() => continue,
+                // receiver is gone when out of time after stop
+                Ok(Err(_)) if self.stopping => continue,
+                task => task??,
+            }
         }
 
         Ok(())
@@ -243,45 +1008,222 @@ impl App {
         info!("{ok}");
         println!("{}", ok.green());
 
-        let failed = format!("failed: {}({})", self.failed.len(), self.failed.join(", "));
+        let names = self
+            .failed
+            .iter()
+            .map(|failure| failure.id.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let failed = format!("failed: {}({})", self.failed.len(), names);
         info!("{failed}");
         println!("{}", failed.red());
+
+        if !self.kept.is_empty() {
+            let kept = format!("kept: {}({})", self.kept.len(), self.kept.join(", "));
+            info!("{kept}");
+            println!("{}", kept.yellow());
+        }
+
+        if !self.skipped.is_empty() {
+            let skipped = format!(
+                "interrupted: {}({})",
+                self.skipped.len(),
+                self.skipped.join(", ")
+            );
+            info!("{skipped}");
+            println!("{}", skipped.yellow());
+        }
+
+        if !self.dry_run {
+            let notice = Notice::Run {
+                ok: self.succeed.clone(),
+                failed: self
+                    .failed
+                    .iter()
+                    .map(|failure| failure.id.clone())
+                    .collect(),
+                kept: self.kept.clone(),
+                interrupted: self.skipped.clone(),
+            };
+            self.helper.notifier.send(notice).await;
+        }
+    }
+
+    async fn try_add_video_file(&mut self, file: &Path) {
+        if let Err(err) = self.add_video_file(file).await {
+            warn!("skip {}, caused by {err:?}", file.display());
+            self.bar.message(format!(
+                "skip {}\n{}",
+                file.display(),
+                format!("{err:?}").red()
+            ));
+        }
+    }
+
+    async fn add_video_file(&mut self, file: &Path) -> Result<()> {
+        // failed videos should not be retried on every run
+        if !self.force
+            && let Some(ref failed_path) = self.config.output.failed_path
+            && file.starts_with(failed_path)
+        {
+            return Ok(());
+        }
+
+        let name = match file.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+
+        let (file_name, ext) = match name.split_once('.') {
+            Some(res) => res,
+            None => return Ok(()),
+        };
+
+        if self.config.input.exts_of(file).iter().any(|e| e == ext) {
+            let sidecar = file.with_file_name(format!("{file_name}.{}", Sidecar::EXT));
+            let fingerprint = Fingerprint::of(file, &sidecar).await?;
+            if !self.force
+                && let Some(record) = self.ledger.get(file, &fingerprint)
+            {
+                info!(
+                    "skip {}, already done as {} in previous run",
+                    file.display(),
+                    record.name
+                );
+                self.known += 1;
+                return Ok(());
+            }
+            self.fingerprints.insert(file.to_path_buf(), fingerprint);
+
+            let (mut video_ty, idx) = VideoType::parse(file_name);
+
+            if sidecar.is_file() {
+                let sidecar = Sidecar::load(&sidecar)
+                    .await
+                    .with_context(|| "load sidecar")?;
+                if let Some(ref id) = sidecar.id {
+                    let (ty, _) = VideoType::parse(id);
+                    info!("override {video_ty} to {ty} by sidecar");
+                    video_ty = ty;
+                }
+                self.sidecars.insert(video_ty.clone(), sidecar);
+            }
+
+            let video = self
+                .videos
+                .entry(video_ty.clone())
+                .or_insert(Video::new(video_ty));
+            video.add_file(
+                VideoFile::builder()
+                    .location(file)
+                    .ext(ext)
+                    .idx(idx)
+                    .build(),
+            );
+        }
+
+        Ok(())
     }
 
     async fn load_all_videos(&mut self) -> Result<()> {
         let input = &self.config.input;
-        for file in Self::walk_dir(&input.path, &input.excludes)
-            .await
-            .with_context(|| "walk dir")?
-        {
-            let name = match file.file_name().and_then(|name| name.to_str()) {
-                Some(name) => name,
-                None => continue,
+        // sources may be nested, files are collected once and checked against their own source
+        let mut files = BTreeSet::new();
+        for path in input.paths() {
+            let found = Self::walk_dir(path, input.excludes_of(path))
+                .await
+                .with_context(|| format!("walk dir {}", path.display()))?;
+            files.extend(found.into_iter().filter(|file| !input.is_excluded(file)));
+        }
+        for file in files {
+            self.try_add_video_file(&file).await;
+        }
+
+        let videos = self
+            .videos
+            .values()
+            .map(|video| video.ty().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let summary = format!("found videos: {}({})", self.videos.len(), videos);
+        info!("{summary}");
+        self.bar.message(summary);
+        if self.known > 0 {
+            let known = format!(
+                "skip {} file(s) handled in previous runs, use --force to process them again",
+                self.known
+            );
+            info!("{known}");
+            self.bar.message(known.yellow().to_string());
+        }
+
+        Ok(())
+    }
+
+    async fn load_library_videos(&mut self) -> Result<()> {
+        // libraries may be nested, files are collected once
+        let mut files = BTreeSet::new();
+        for library in self.config.libraries() {
+            let found = Self::walk_dir(library, &[])
+                .await
+                .with_context(|| format!("walk dir {}", library.display()))?;
+            files.extend(found);
+        }
+        let exts = self.config.input.all_exts();
+
+        let mut names = Vec::new();
+        let mut videos = HashMap::new();
+        for file in files.iter() {
+            let (Some(parent), Some(name)) = (
+                file.parent(),
+                file.file_name().and_then(|name| name.to_str()),
+            ) else {
+                continue;
             };
 
-            let (file_name, ext) = match name.split_once('.') {
-                Some(res) => res,
-                None => continue,
+            if let Some(stem) = name.strip_suffix(".nfo") {
+                let (video_ty, _) = VideoType::parse(stem);
+                names.push((parent, video_ty));
+                continue;
+            }
+
+            let Some((file_name, ext)) = name.split_once('.') else {
+                continue;
             };
+            if !exts.contains(&ext) {
+                continue;
+            }
+
+            let (video_ty, idx) = VideoType::parse(file_name);
+            let video = videos
+                .entry((parent, video_ty.clone()))
+                .or_insert(Video::new(video_ty));
+            video.add_file(
+                VideoFile::builder()
+                    .location(file)
+                    .ext(ext)
+                    .idx(idx)
+                    .build(),
+            );
+        }
 
-            if input.exts.iter().any(|e| e == ext) {
-                let (video_ty, idx) = VideoType::parse(file_name);
-
-                let video = self
-                    .videos
-                    .entry(video_ty.clone())
-                    .or_insert(Video::new(video_ty));
-                video.add_file(
-                    VideoFile::builder()
-                        .location(&file)
-                        .ext(ext)
-                        .idx(idx)
-                        .build(),
+        for key in names {
+            let Some(video) = videos.remove(&key) else {
+                warn!(
+                    "no video found next to nfo of {} in {}",
+                    key.1,
+                    key.0.display()
                 );
+                continue;
+            };
+
+            if self.videos.contains_key(&key.1) {
+                warn!("skip duplicated {} in {}", key.1, key.0.display());
+                continue;
             }
+            self.videos.insert(key.1, video);
         }
 
-        self.bar.set_total(self.videos.len()).await;
         let videos = self
             .videos
             .values()
@@ -10,6 +10,7 @@ pub struct Bar {
     cnt: Arc<RwLock<usize>>,
     total: Arc<Mutex<usize>>,
     should_quit: Arc<RwLock<bool>>,
+    paused: Arc<RwLock<bool>>,
     notify: Arc<Notify>,
     disabled: bool,
 }
@@ -21,6 +22,7 @@ impl Bar {
             total: Arc::new(Mutex::new(0)),
             cnt: Arc::new(RwLock::new(0)),
             should_quit: Arc::new(RwLock::new(false)),
+            paused: Arc::new(RwLock::new(false)),
             notify: Arc::new(Notify::new()),
             disabled,
         };
@@ -29,9 +31,9 @@ impl Bar {
         bar
     }
 
-    pub async fn set_total(&self, total: usize) {
+    pub async fn add_total(&self, total: usize) {
         let mut t = self.total.lock().await;
-        *t = total;
+        *t += total;
     }
 
     async fn start(&self) {
@@ -40,6 +42,7 @@ impl Bar {
         }
 
         let should_quit = self.should_quit.clone();
+        let paused = self.paused.clone();
         let notify = self.notify.clone();
         let cnt = self.cnt.clone();
         let total = self.total.clone();
@@ -51,27 +54,29 @@ impl Bar {
             let line_len = *app::LINE_LENGTH - 20;
 
             loop {
-                let total = { *total.lock().await };
-                let cnt = { *cnt.read().await };
-                let per = if total == 0 { 0 } else { cnt * 100 / total };
-                let p = per * line_len / 100;
-                print!(
-                    "\r{}",
-                    format!(
-                        "{spinner}|{per}%|{fill:░<line_len$}|[{cnt}/{total}]",
-                        spinner = bar[idx],
-                        fill = "█".repeat(p),
-                        total = if total == 0 {
-                            "?".to_string()
-                        } else {
-                            total.to_string()
-                        }
-                    )
-                    .yellow()
-                );
-                io::stdout().flush().ok();
-                idx += 1;
-                idx %= bar_len;
+                if !*paused.read().await {
+                    let total = { *total.lock().await };
+                    let cnt = { *cnt.read().await };
+                    let per = if total == 0 { 0 } else { cnt * 100 / total };
+                    let p = per * line_len / 100;
+                    print!(
+                        "\r{}",
+                        format!(
+                            "{spinner}|{per}%|{fill:░<line_len$}|[{cnt}/{total}]",
+                            spinner = bar[idx],
+                            fill = "█".repeat(p),
+                            total = if total == 0 {
+                                "?".to_string()
+                            } else {
+                                total.to_string()
+                            }
+                        )
+                        .yellow()
+                    );
+                    io::stdout().flush().ok();
+                    idx += 1;
+                    idx %= bar_len;
+                }
                 time::sleep(interval).await;
                 if *should_quit.read().await {
                     break;
@@ -94,6 +99,23 @@ impl Bar {
         print!("\r{}\r", " ".repeat(*app::LINE_LENGTH));
     }
 
+    /// stop drawing the bar, so that user can be prompted
+    pub async fn pause(&self) {
+        {
+            let mut paused = self.paused.write().await;
+            *paused = true;
+        }
+        if !self.disabled {
+            print!("\r{}\r", " ".repeat(*app::LINE_LENGTH));
+            io::stdout().flush().ok();
+        }
+    }
+
+    pub async fn resume(&self) {
+        let mut paused = self.paused.write().await;
+        *paused = false;
+    }
+
     pub fn message(&self, msg: impl AsRef<str>) {
         let msg = msg.as_ref();
         if self.disabled {
@@ -103,6 +125,13 @@ impl Bar {
         }
     }
 
+    /// videos done and videos found
+    pub async fn progress(&self) -> (usize, usize) {
+        let cnt = { *self.cnt.read().await };
+        let total = { *self.total.lock().await };
+        (cnt, total)
+    }
+
     pub async fn add(&self) {
         let mut cnt = self.cnt.write().await;
         *cnt += 1;
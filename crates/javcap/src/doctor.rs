@@ -0,0 +1,459 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+use config::{Config, Conflict};
+use log::{error, info, warn};
+use tokio::fs;
+use validator::Validate;
+use video::{Video, VideoFile, VideoType};
+
+use super::bar::Bar;
+use super::helper::Helper;
+use super::journal::JournalKind;
+use super::library::NfoInfo;
+use super::payload::{Artifact, Payload};
+
+enum Problem {
+    EmptyDir,
+    MissingNfo,
+    MissingImage(Artifact),
+    BrokenImage(Artifact, &'static str),
+    BrokenNfo(String),
+    MissingParts(Vec<u32>),
+    NoVideo,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::EmptyDir => write!(f, "empty dir"),
+            Problem::MissingNfo => write!(f, "missing nfo"),
+            Problem::MissingImage(artifact) => write!(f, "missing {artifact}"),
+            Problem::BrokenImage(artifact, reason) => write!(f, "broken {artifact}, {reason}"),
+            Problem::BrokenNfo(reason) => write!(f, "broken nfo, {reason}"),
+            Problem::MissingParts(parts) => write!(
+                f,
+                "missing part(s) {}",
+                parts
+                    .iter()
+                    .map(|part| format!("CD{part}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Problem::NoVideo => write!(f, "no video"),
+        }
+    }
+}
+
+/// files of one video in the library
+#[derive(Default)]
+struct Entry {
+    nfo: Option<PathBuf>,
+    poster: Option<PathBuf>,
+    fanart: Option<PathBuf>,
+    videos: Vec<VideoFile>,
+}
+
+/// check the library in output path, fix what can be fixed if asked, return
+/// the count of problems left
+pub async fn doctor(config: &Config, fix: bool) -> Result<usize> {
+    let libraries = config.libraries();
+    let mut files = Vec::new();
+    let mut empty_dirs = Vec::new();
+    for output in libraries.iter() {
+        if !output.is_dir() {
+            bail!("output path {} is not a dir", output.display());
+        }
+
+        scan(output, &mut files, &mut empty_dirs)
+            .await
+            .with_context(|| format!("scan {}", output.display()))?;
+    }
+    // output paths themselves are never removed
+    empty_dirs.retain(|dir| !libraries.contains(&dir.as_path()));
+    files.sort();
+    files.dedup();
+    empty_dirs.sort();
+    empty_dirs.dedup();
+    let exts = config.input.all_exts();
+
+    let mut entries = BTreeMap::<(PathBuf, String), (VideoType, Entry)>::new();
+    for file in files.iter() {
+        let (Some(parent), Some(name)) = (
+            file.parent(),
+            file.file_name().and_then(|name| name.to_str()),
+        ) else {
+            continue;
+        };
+
+        let (stem, kind) = if let Some(stem) = name.strip_suffix("-poster.jpg") {
+            (stem, Some(Artifact::Poster))
+        } else if let Some(stem) = name.strip_suffix("-fanart.jpg") {
+            (stem, Some(Artifact::Fanart))
+        } else if let Some(stem) = name.strip_suffix(".nfo") {
+            (stem, Some(Artifact::Nfo))
+        } else {
+            match name.split_once('.') {
+                Some((stem, ext)) if exts.contains(&ext) => (stem, None),
+                _ => continue,
+            }
+        };
+
+        let (ty, idx) = VideoType::parse(stem);
+        let (_, entry) = entries
+            .entry((parent.to_path_buf(), ty.to_string()))
+            .or_insert_with(|| (ty, Entry::default()));
+        match kind {
+            Some(Artifact::Poster) => entry.poster = Some(file.clone()),
+            Some(Artifact::Fanart) => entry.fanart = Some(file.clone()),
+            Some(Artifact::Nfo) => entry.nfo = Some(file.clone()),
+            Some(Artifact::Subtitle) => {}
+            None => {
+                let ext = name.split_once('.').map(|(_, ext)| ext).unwrap_or_default();
+                entry.videos.push(
+                    VideoFile::builder()
+                        .location(file)
+                        .ext(ext)
+                        .idx(idx)
+                        .build(),
+                );
+            }
+        }
+    }
+
+    let mut problems = 0;
+    for dir in empty_dirs.iter() {
+        report(dir, &Problem::EmptyDir);
+        problems += 1;
+    }
+
+    // entries with readable nfo and bad images, which can be downloaded again
+    let mut fixable = Vec::new();
+    for ((dir, name), (ty, entry)) in entries {
+        let path = dir.join(&name);
+        let mut found = Vec::new();
+        let mut readable = false;
+        match entry.nfo {
+            None => found.push(Problem::MissingNfo),
+            Some(ref nfo) => match NfoInfo::read(nfo).await {
+                Ok(_) => readable = true,
+                Err(e) => found.push(Problem::BrokenNfo(format!("{e:#}"))),
+            },
+        }
+
+        let mut missing = Vec::new();
+        for (artifact, image) in [
+            (Artifact::Poster, &entry.poster),
+            (Artifact::Fanart, &entry.fanart),
+        ] {
+            let Some(image) = image else {
+                found.push(Problem::MissingImage(artifact));
+                missing.push(artifact);
+                continue;
+            };
+            let bytes = fs::read(image)
+                .await
+                .with_context(|| format!("read {}", image.display()))?;
+            if let Some(reason) = check_image(&bytes) {
+                found.push(Problem::BrokenImage(artifact, reason));
+                missing.push(artifact);
+            }
+        }
+
+        if entry.videos.is_empty() {
+            found.push(Problem::NoVideo);
+        } else {
+            let gaps = missing_parts(&entry.videos);
+            if !gaps.is_empty() {
+                found.push(Problem::MissingParts(gaps));
+            }
+        }
+
+        for problem in found.iter() {
+            report(&path, problem);
+        }
+        problems += found.len();
+
+        if readable && !missing.is_empty() {
+            let mut video = Video::new(ty);
+            for file in entry.videos {
+                video.add_file(file);
+            }
+            fixable.push((dir, video, missing));
+        }
+    }
+
+    if problems == 0 {
+        info!("no problem found in {} library(s)", libraries.len());
+        println!("{}", "no problem found".green());
+        return Ok(0);
+    }
+    println!("{}", format!("{problems} problem(s) found").red());
+    if !fix {
+        return Ok(problems);
+    }
+
+    let helper = Helper::new(config, None, JournalKind::Doctor).with_context(|| "build helper")?;
+    let mut fixed = 0;
+    // sorted, so that sub dirs are removed before their parent, and a dir which is
+    // not empty anymore is kept
+    for dir in empty_dirs.iter().rev() {
+        match helper.journal.remove_dir(dir).await {
+            Ok(_) => {
+                println!("{} {}", "removed".green(), dir.display());
+                fixed += 1;
+            }
+            Err(e) => {
+                warn!("could not remove {}, caused by {e:#}", dir.display());
+                eprintln!("{} {}: {e:#}", "could not remove".red(), dir.display());
+            }
+        }
+    }
+
+    if !fixable.is_empty() {
+        let bar = Arc::new(Bar::new().await);
+        bar.add_total(fixable.len()).await;
+        for (dir, video, missing) in fixable {
+            let name = video.ty().to_string();
+            match download_images(&helper, &bar, &dir, video, &missing).await {
+                Ok(written) => fixed += written,
+                Err(e) => {
+                    error!("could not fix {name}, caused by {e:?}");
+                    bar.message(format!(
+                        "{} {name}\n{}",
+                        "could not fix".red(),
+                        format!("{e:?}").red()
+                    ));
+                }
+            }
+            bar.add().await;
+        }
+        bar.finish().await;
+    }
+
+    let left = problems.saturating_sub(fixed);
+    println!(
+        "{}",
+        format!("{fixed} problem(s) fixed, {left} left").yellow()
+    );
+
+    Ok(left)
+}
+
+fn report(path: &Path, problem: &Problem) {
+    warn!("{}: {problem}", path.display());
+    println!("{}: {}", path.display(), problem.to_string().red());
+}
+
+/// download the images again, return the count of images written
+async fn download_images(
+    helper: &Helper,
+    bar: &Arc<Bar>,
+    dir: &Path,
+    video: Video,
+    artifacts: &[Artifact],
+) -> Result<usize> {
+    bar.message(format!(
+        "download images of {} in {}",
+        video.ty(),
+        dir.display()
+    ));
+    let mut nfo = helper
+        .spider
+        .find(video.ty().clone())
+        .await
+        .with_context(|| "find video")?;
+    nfo.auto_fix_by_key(video.ty());
+    nfo.validate().with_context(|| "validate nfo")?;
+
+    // never replace a broken image with another broken one
+    let mut good = Vec::new();
+    for artifact in artifacts {
+        let bytes = match artifact {
+            Artifact::Poster => nfo.poster(),
+            Artifact::Fanart => nfo.fanart(),
+            _ => continue,
+        };
+        match check_image(bytes) {
+            Some(reason) => {
+                warn!(
+                    "downloaded {artifact} of {} is broken, {reason}, skip",
+                    video.ty()
+                );
+                bar.message(format!(
+                    "{artifact} ... {}",
+                    format!("skip, {reason}").yellow()
+                ));
+            }
+            None => good.push(*artifact),
+        }
+    }
+    if good.is_empty() {
+        return Ok(0);
+    }
+
+    let payload = Payload::builder()
+        .video(video)
+        .nfo(nfo)
+        .bar(bar.clone())
+        .journal(helper.journal.clone())
+        .build();
+    payload
+        .write_some_to(dir, &good, Conflict::Overwrite)
+        .await
+        .with_context(|| format!("write images to {}", dir.display()))?;
+
+    Ok(good.len())
+}
+
+/// collect all files, and dirs without any file inside, sub dirs included
+async fn scan(dir: &Path, files: &mut Vec<PathBuf>, empty_dirs: &mut Vec<PathBuf>) -> Result<bool> {
+    let mut entries = fs::read_dir(dir)
+        .await
+        .with_context(|| format!("read dir in {}", dir.display()))?;
+    let mut has_file = false;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.is_dir() {
+            has_file |= Box::pin(scan(&path, files, empty_dirs)).await?;
+        } else {
+            files.push(path);
+            has_file = true;
+        }
+    }
+
+    if !has_file {
+        empty_dirs.push(dir.to_path_buf());
+    }
+
+    Ok(has_file)
+}
+
+/// parts before the last one which are not found, like CD2 of CD1 and CD3
+fn missing_parts(videos: &[VideoFile]) -> Vec<u32> {
+    let parts = videos
+        .iter()
+        .map(|video| *video.idx())
+        .collect::<BTreeSet<_>>();
+    let max = parts.iter().max().copied().unwrap_or_default();
+
+    (1..=max).filter(|idx| !parts.contains(idx)).collect()
+}
+
+/// look into the bytes, return the reason if it is not a complete image
+fn check_image(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.is_empty() {
+        return Some("zero bytes");
+    }
+
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        // some encoders pad after the end marker
+        let end = bytes
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .map(|idx| &bytes[..=idx])
+            .unwrap_or_default();
+        return if end.ends_with(&[0xFF, 0xD9]) {
+            None
+        } else {
+            Some("truncated jpeg")
+        };
+    }
+    if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        return if bytes.windows(4).rev().take(64).any(|w| w == b"IEND") {
+            None
+        } else {
+            Some("truncated png")
+        };
+    }
+    if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        return None;
+    }
+    if bytes.starts_with(b"GIF8") {
+        return None;
+    }
+
+    Some("unknown format")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_check_image() {
+        assert_eq!(check_image(&[]), Some("zero bytes"));
+        assert_eq!(check_image(&[0xFF, 0xD8, 0xFF, 0xE0, 0xFF, 0xD9]), None);
+        assert_eq!(
+            check_image(&[0xFF, 0xD8, 0xFF, 0xE0, 0xFF, 0xD9, 0, 0]),
+            None
+        );
+        assert_eq!(
+            check_image(&[0xFF, 0xD8, 0xFF, 0xE0, 0x00]),
+            Some("truncated jpeg")
+        );
+
+        let png = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        assert_eq!(
+            check_image(&[&png[..], b"IEND\xAE\x42\x60\x82"].concat()),
+            None
+        );
+        assert_eq!(check_image(&png), Some("truncated png"));
+
+        assert_eq!(check_image(b"GIF89a"), None);
+        assert_eq!(check_image(b"<html>"), Some("unknown format"));
+    }
+
+    #[test]
+    fn test_missing_parts() {
+        let videos = |parts: &[u32]| {
+            parts
+                .iter()
+                .map(|idx| {
+                    VideoFile::builder()
+                        .location(Path::new(&format!("a-cd{idx}.mp4")))
+                        .ext("mp4")
+                        .idx(*idx)
+                        .build()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(missing_parts(&videos(&[0])), Vec::<u32>::new());
+        assert_eq!(missing_parts(&videos(&[1, 2])), Vec::<u32>::new());
+        assert_eq!(missing_parts(&videos(&[1, 3])), vec![2]);
+        assert_eq!(missing_parts(&videos(&[4])), vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_scan() -> Result<()> {
+        let dir = temp_dir("doctor-scan");
+        fs::create_dir_all(dir.join("a")).await?;
+        fs::write(dir.join("a").join("a.mp4"), "").await?;
+        fs::create_dir_all(dir.join("b").join("c")).await?;
+        fs::create_dir_all(dir.join("a").join("d")).await?;
+
+        let mut files = Vec::new();
+        let mut empty_dirs = Vec::new();
+        assert!(scan(&dir, &mut files, &mut empty_dirs).await?);
+        empty_dirs.sort();
+        assert_eq!(files, vec![dir.join("a").join("a.mp4")]);
+        assert_eq!(
+            empty_dirs,
+            vec![
+                dir.join("a").join("d"),
+                dir.join("b"),
+                dir.join("b").join("c"),
+            ]
+        );
+
+        fs::remove_dir_all(dir).await.ok();
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use super::helper::cache_dir;
+
+/// a video failed in a run and not handled again since then
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Failure {
+    pub id: String,
+    pub files: Vec<PathBuf>,
+    pub error: String,
+}
+
+impl Failure {
+    fn path() -> PathBuf {
+        cache_dir().join("failed")
+    }
+
+    pub async fn load_all() -> Result<Vec<Failure>> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("read {}", path.display()))?;
+        let failures =
+            serde_json::from_str(&content).with_context(|| format!("decode {}", path.display()))?;
+
+        Ok(failures)
+    }
+
+    pub async fn save_all(failures: &[Failure]) -> Result<()> {
+        let path = Self::path();
+        let content = serde_json::to_string_pretty(failures).with_context(|| "encode failures")?;
+        fs::write(&path, content)
+            .await
+            .with_context(|| format!("write {}", path.display()))?;
+
+        Ok(())
+    }
+}
@@ -1,26 +1,108 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
 use anyhow::{Context, Result};
 use config::Config;
-use spider::Spider;
+use log::info;
+use nfo::Nfo;
+use spider::{Picker, Spider};
 use tokio::sync::Semaphore;
 use translator::Translator;
 
+use super::journal::{Journal, JournalKind};
+use super::refresh::Refresher;
+use super::webhook::Notifier;
+
 pub struct Helper {
     pub sema: Semaphore,
     pub spider: Spider,
     pub translator: Translator,
+    pub journal: Arc<Journal>,
+    pub notifier: Notifier,
+    pub refresher: Refresher,
 }
 
 impl Helper {
-    pub fn new(config: &Config) -> Result<Helper> {
+    pub fn new(
+        config: &Config,
+        picker: Option<Arc<dyn Picker>>,
+        journal: JournalKind,
+    ) -> Result<Helper> {
         let sema = Semaphore::new(config.task_limit);
-        let spider = Spider::new(config).with_context(|| "build spider")?;
+        let spider = Spider::new(config, picker).with_context(|| "build spider")?;
         let translator = Translator::new(config).with_context(|| "build translator")?;
+        let journal = Journal::new(journal.path());
+        let notifier = Notifier::new(config).with_context(|| "build notifier")?;
+        let refresher = Refresher::new(
+            config.media_servers.clone(),
+            Duration::from_secs(config.network.timeout),
+        )
+        .with_context(|| "build refresher")?;
         let helper = Helper {
             sema,
             spider,
             translator,
+            journal: Arc::new(journal),
+            notifier,
+            refresher,
         };
 
         Ok(helper)
     }
+
+    /// translate title and plot, return whether anything is translated
+    pub async fn translate(helper: &Arc<Helper>, nfo: &mut Nfo) -> Result<bool> {
+        let title_task = tokio::spawn({
+            let helper = helper.clone();
+            let title = nfo.title().clone();
+            async move {
+                helper
+                    .translator
+                    .translate(&title)
+                    .await
+                    .with_context(|| format!("translate {title}"))
+            }
+        });
+        let plot_task = tokio::spawn({
+            let helper = helper.clone();
+            let plot = nfo.plot().clone();
+            async move {
+                helper
+                    .translator
+                    .translate(&plot)
+                    .await
+                    .with_context(|| format!("translate {plot}"))
+            }
+        });
+
+        let mut translated = false;
+        if let Some(title) = title_task.await?? {
+            info!("translated {title}");
+            nfo.set_title(title);
+            translated = true;
+        }
+        if let Some(plot) = plot_task.await?? {
+            info!("translated {plot}");
+            nfo.set_plot(plot);
+            translated = true;
+        }
+
+        Ok(translated)
+    }
+}
+
+/// macos -> /Users/<username>/.cache/javcap
+/// linux -> /home/<username>/.cache/javcap
+/// windows -> C:\Users\<username>\.cache\javcap
+pub fn cache_dir() -> PathBuf {
+    let username = whoami::username();
+    #[cfg(target_os = "macos")]
+    let user_dir = PathBuf::from("/Users").join(username);
+    #[cfg(target_os = "linux")]
+    let user_dir = PathBuf::from("/home").join(username);
+    #[cfg(target_os = "windows")]
+    let user_dir = PathBuf::from("C:\\Users").join(username);
+
+    user_dir.join(".cache").join(app::NAME)
 }
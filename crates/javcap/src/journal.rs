@@ -0,0 +1,521 @@
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use super::helper::cache_dir;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op")]
+pub enum Entry {
+    #[serde(rename = "create_dir")]
+    CreateDir { path: PathBuf },
+
+    /// `existed` is only true in journals written before backups, such a file is kept
+    #[serde(rename = "write_file")]
+    WriteFile { path: PathBuf, existed: bool },
+
+    #[serde(rename = "backup")]
+    Backup { path: PathBuf, backup: PathBuf },
+
+    #[serde(rename = "move_file")]
+    MoveFile { from: PathBuf, to: PathBuf },
+
+    #[serde(rename = "remove_dir")]
+    RemoveDir { path: PathBuf },
+}
+
+/// command a journal is kept for, each has its own so that one does not replace another
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum JournalKind {
+    #[default]
+    Run,
+    Rescrape,
+    Doctor,
+}
+
+impl JournalKind {
+    pub fn path(self) -> PathBuf {
+        let name = match self {
+            JournalKind::Run => "journal",
+            JournalKind::Rescrape => "rescrape.journal",
+            JournalKind::Doctor => "doctor.journal",
+        };
+
+        cache_dir().join(name)
+    }
+}
+
+/// record every change to the filesystem in one run
+///
+/// journal file is created by the first record, so a run without any change
+/// keeps the journal of the previous run. a change is recorded before it is
+/// made, so that a change interrupted halfway can still be reverted. a file is
+/// never overwritten or removed, it is moved aside as a backup into a dir beside
+/// the journal instead, so nothing is left in the library. backups are kept for
+/// undo until the journal is replaced by the next run, except discarded files,
+/// which are dropped once the run commits
+pub struct Journal {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+    entries: Mutex<Vec<Entry>>,
+    discarded: Mutex<Vec<PathBuf>>,
+}
+
+impl Journal {
+    pub fn new(path: impl Into<PathBuf>) -> Journal {
+        Journal {
+            path: path.into(),
+            file: Mutex::new(None),
+            entries: Mutex::new(Vec::new()),
+            discarded: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub async fn create_dir(&self, path: &Path) -> Result<()> {
+        self.record(Entry::CreateDir {
+            path: path.to_path_buf(),
+        })
+        .await
+    }
+
+    /// call `backup` first if the file exists
+    pub async fn write_file(&self, path: &Path) -> Result<()> {
+        self.record(Entry::WriteFile {
+            path: path.to_path_buf(),
+            existed: path.exists(),
+        })
+        .await
+    }
+
+    /// move the file aside before it is replaced or removed, undo puts it back
+    pub async fn backup(&self, path: &Path) -> Result<()> {
+        self.move_aside(path).await?;
+
+        Ok(())
+    }
+
+    /// remove a file which is not needed once the run commits, it can still be
+    /// rolled back before that
+    pub async fn discard(&self, path: &Path) -> Result<()> {
+        let backup = self.move_aside(path).await?;
+        self.discarded.lock().await.push(backup);
+
+        Ok(())
+    }
+
+    /// drop the files discarded in the run, which can not be undone anymore
+    pub async fn commit(&self) {
+        let discarded = mem::take(&mut *self.discarded.lock().await);
+        for backup in discarded {
+            if !backup.exists() {
+                continue;
+            }
+            match fs::remove_file(&backup).await {
+                Ok(_) => info!("drop {}", backup.display()),
+                Err(e) => warn!("could not drop {}, caused by {e}", backup.display()),
+            }
+        }
+        // only if nothing is kept for undo
+        fs::remove_dir(Self::backup_dir(&self.path)).await.ok();
+    }
+
+    async fn move_aside(&self, path: &Path) -> Result<PathBuf> {
+        let dir = Self::backup_dir(&self.path);
+        let backup = Self::backup_of(&dir, path);
+        self.record(Entry::Backup {
+            path: path.to_path_buf(),
+            backup: backup.clone(),
+        })
+        .await?;
+        // after the record, which drops the backups of the previous run
+        if !dir.exists() {
+            fs::create_dir_all(&dir)
+                .await
+                .with_context(|| format!("create dir {}", dir.display()))?;
+        }
+        move_across(path, &backup)
+            .await
+            .with_context(|| format!("move {} to {}", path.display(), backup.display()))?;
+        info!("backup {} to {}", path.display(), backup.display());
+
+        Ok(backup)
+    }
+
+    /// backups of a journal, like `journal.backup` for `journal`
+    fn backup_dir(path: &Path) -> PathBuf {
+        path.with_extension("backup")
+    }
+
+    fn backup_of(dir: &Path, path: &Path) -> PathBuf {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        (0..)
+            .map(|idx| match idx {
+                0 => dir.join(format!("{name}.bak")),
+                idx => dir.join(format!("{name}.{idx}.bak")),
+            })
+            .find(|backup| !backup.exists())
+            .unwrap_or_else(|| dir.join(format!("{name}.bak")))
+    }
+
+    /// remove an empty dir, never anything inside, undo creates it again
+    pub async fn remove_dir(&self, path: &Path) -> Result<()> {
+        self.record(Entry::RemoveDir {
+            path: path.to_path_buf(),
+        })
+        .await?;
+        fs::remove_dir(path)
+            .await
+            .with_context(|| format!("remove dir {}", path.display()))?;
+        info!("remove dir {}", path.display());
+
+        Ok(())
+    }
+
+    pub async fn move_file(&self, from: &Path, to: &Path) -> Result<()> {
+        self.record(Entry::MoveFile {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        })
+        .await
+    }
+
+    async fn record(&self, entry: Entry) -> Result<()> {
+        let mut guard = self.file.lock().await;
+        let file = match guard.take() {
+            Some(file) => file,
+            None => {
+                Self::drop_backups(&self.path).await;
+                OpenOptions::new()
+                    .create(true)
+                    .truncate(true)
+                    .write(true)
+                    .open(&self.path)
+                    .await
+                    .with_context(|| format!("open {}", self.path.display()))?
+            }
+        };
+        let file = guard.insert(file);
+
+        let mut line = serde_json::to_string(&entry).with_context(|| "encode entry")?;
+        line.push('\n');
+        file.write_all(line.as_bytes())
+            .await
+            .with_context(|| "write entry")?;
+        file.flush().await.with_context(|| "flush journal")?;
+        self.entries.lock().await.push(entry);
+
+        Ok(())
+    }
+
+    /// remove backups in the journal, which can not be undone once it is replaced
+    async fn drop_backups(path: &Path) {
+        let dir = Self::backup_dir(path);
+        let Ok(entries) = Self::load(path).await else {
+            fs::remove_dir_all(&dir).await.ok();
+            return;
+        };
+        for entry in entries {
+            let Entry::Backup { backup, .. } = entry else {
+                continue;
+            };
+            if !backup.exists() {
+                continue;
+            }
+            match fs::remove_file(&backup).await {
+                Ok(_) => info!("remove backup {}", backup.display()),
+                Err(e) => warn!(
+                    "could not remove backup {}, caused by {e}",
+                    backup.display()
+                ),
+            }
+        }
+        fs::remove_dir(&dir).await.ok();
+    }
+
+    async fn load(path: &Path) -> Result<Vec<Entry>> {
+        let content = fs::read_to_string(path)
+            .await
+            .with_context(|| format!("read journal {}", path.display()))?;
+        let mut entries = Vec::new();
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let entry = serde_json::from_str::<Entry>(line)
+                .with_context(|| format!("decode entry {line}"))?;
+            entries.push(entry);
+        }
+
+        Ok(entries)
+    }
+
+    /// position in the journal, changes after it can be reverted by `rollback`
+    pub async fn mark(&self) -> usize {
+        self.entries.lock().await.len()
+    }
+
+    /// revert changes recorded after the mark, return the count of entries failed to revert
+    pub async fn rollback(&self, mark: usize) -> usize {
+        let entries = {
+            let mut entries = self.entries.lock().await;
+            let mark = mark.min(entries.len());
+            entries.split_off(mark)
+        };
+
+        let mut failed = 0;
+        for entry in entries.into_iter().rev() {
+            match Self::revert(&entry).await {
+                Ok(msg) => info!("{msg}"),
+                Err(err) => {
+                    warn!("failed to revert {entry:?}, caused by {err:?}");
+                    failed += 1;
+                }
+            }
+        }
+
+        failed
+    }
+
+    /// replay the journal in reverse, return the count of entries failed to revert
+    pub async fn undo(path: &Path) -> Result<usize> {
+        let entries = Self::load(path).await?;
+
+        let mut failed = 0;
+        for entry in entries.into_iter().rev() {
+            match Self::revert(&entry).await {
+                Ok(msg) => {
+                    info!("{msg}");
+                    println!("{msg}");
+                }
+                Err(err) => {
+                    warn!("failed to revert {entry:?}, caused by {err:?}");
+                    eprintln!("failed to revert {entry:?}, caused by {err:?}");
+                    failed += 1;
+                }
+            }
+        }
+
+        if failed == 0 {
+            fs::remove_file(path)
+                .await
+                .with_context(|| format!("remove journal {}", path.display()))?;
+            fs::remove_dir(Self::backup_dir(path)).await.ok();
+        }
+
+        Ok(failed)
+    }
+
+    async fn revert(entry: &Entry) -> Result<String> {
+        match entry {
+            Entry::CreateDir { path } => {
+                if !path.exists() {
+                    return Ok(format!("dir already removed {}", path.display()));
+                }
+                fs::remove_dir(path)
+                    .await
+                    .with_context(|| format!("remove dir {}", path.display()))?;
+
+                Ok(format!("remove dir {}", path.display()))
+            }
+            Entry::WriteFile { path, existed } => {
+                if *existed {
+                    return Ok(format!("keep overwritten file {}", path.display()));
+                }
+                if !path.exists() {
+                    return Ok(format!("file already removed {}", path.display()));
+                }
+                fs::remove_file(path)
+                    .await
+                    .with_context(|| format!("remove file {}", path.display()))?;
+
+                Ok(format!("remove file {}", path.display()))
+            }
+            Entry::Backup { path, backup } => {
+                if !backup.exists() {
+                    return Ok(format!(
+                        "backup already restored or dropped {}",
+                        path.display()
+                    ));
+                }
+                move_across(backup, path)
+                    .await
+                    .with_context(|| format!("move {} to {}", backup.display(), path.display()))?;
+
+                Ok(format!("restore {}", path.display()))
+            }
+            Entry::MoveFile { from, to } => {
+                if from.exists() {
+                    return Ok(format!("file already restored {}", from.display()));
+                }
+                if let Some(parent) = from.parent()
+                    && !parent.exists()
+                {
+                    fs::create_dir_all(parent)
+                        .await
+                        .with_context(|| format!("create dir {}", parent.display()))?;
+                }
+                move_across(to, from)
+                    .await
+                    .with_context(|| format!("move {} to {}", to.display(), from.display()))?;
+
+                Ok(format!("move {} to {}", to.display(), from.display()))
+            }
+            Entry::RemoveDir { path } => {
+                if path.exists() {
+                    return Ok(format!("dir already created {}", path.display()));
+                }
+                fs::create_dir_all(path)
+                    .await
+                    .with_context(|| format!("create dir {}", path.display()))?;
+
+                Ok(format!("create dir {}", path.display()))
+            }
+        }
+    }
+}
+
+/// rename, or copy and remove if the paths are on different filesystems
+pub async fn move_across(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to).await {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            fs::copy(from, to).await?;
+            fs::remove_file(from).await
+        }
+        res => res,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+    use pretty_assertions::assert_eq;
+
+    /// a run creating a dir, writing a new file, overwriting an old one, moving a video and
+    /// removing an empty dir
+    async fn run(dir: &Path, journal: &Journal) -> Result<()> {
+        let out = dir.join("out");
+        journal.create_dir(&out).await?;
+        fs::create_dir(&out).await?;
+
+        let nfo = out.join("a.nfo");
+        journal.write_file(&nfo).await?;
+        fs::write(&nfo, "new nfo").await?;
+
+        let poster = dir.join("poster.jpg");
+        journal.backup(&poster).await?;
+        journal.write_file(&poster).await?;
+        fs::write(&poster, "new poster").await?;
+
+        let video = dir.join("a.mp4");
+        journal.move_file(&video, &out.join("a.mp4")).await?;
+        fs::rename(&video, out.join("a.mp4")).await?;
+
+        journal.remove_dir(&dir.join("empty")).await?;
+
+        Ok(())
+    }
+
+    async fn prepare(dir: &Path) -> Result<()> {
+        fs::write(dir.join("poster.jpg"), "old poster").await?;
+        fs::write(dir.join("a.mp4"), "video").await?;
+        fs::create_dir(dir.join("empty")).await?;
+
+        Ok(())
+    }
+
+    async fn assert_reverted(dir: &Path) -> Result<()> {
+        assert!(!dir.join("out").exists());
+        assert_eq!(
+            fs::read_to_string(dir.join("poster.jpg")).await?,
+            "old poster"
+        );
+        assert_eq!(fs::read_to_string(dir.join("a.mp4")).await?, "video");
+        assert!(!dir.join("journal.backup").join("poster.jpg.bak").exists());
+        assert!(dir.join("empty").is_dir());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_undo() -> Result<()> {
+        let dir = temp_dir("journal-undo");
+        prepare(&dir).await?;
+        let path = dir.join("journal");
+        run(&dir, &Journal::new(&path)).await?;
+        assert!(dir.join("journal.backup").join("poster.jpg.bak").exists());
+
+        assert_eq!(Journal::undo(&path).await?, 0);
+        assert_reverted(&dir).await?;
+        assert!(!path.exists());
+
+        fs::remove_dir_all(dir).await.ok();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rollback() -> Result<()> {
+        let dir = temp_dir("journal-rollback");
+        prepare(&dir).await?;
+        let journal = Journal::new(dir.join("journal"));
+        fs::write(dir.join("kept.txt"), "kept").await?;
+        journal.write_file(&dir.join("kept.txt")).await?;
+
+        let mark = journal.mark().await;
+        run(&dir, &journal).await?;
+        assert_eq!(journal.rollback(mark).await, 0);
+        assert_reverted(&dir).await?;
+        // changes before the mark are left alone
+        assert!(dir.join("kept.txt").exists());
+
+        fs::remove_dir_all(dir).await.ok();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_drop_backups() -> Result<()> {
+        let dir = temp_dir("journal-drop");
+        prepare(&dir).await?;
+        let path = dir.join("journal");
+        run(&dir, &Journal::new(&path)).await?;
+
+        // next run replaces the journal, backups of the last run can not be undone anymore
+        let journal = Journal::new(&path);
+        journal.create_dir(&dir.join("next")).await?;
+        assert!(!dir.join("journal.backup").join("poster.jpg.bak").exists());
+
+        fs::remove_dir_all(dir).await.ok();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_discard() -> Result<()> {
+        let dir = temp_dir("journal-discard");
+        let file = dir.join("a.mp4");
+        fs::write(&file, "stale").await?;
+        let journal = Journal::new(dir.join("journal"));
+
+        // still there for a rollback before commit
+        let mark = journal.mark().await;
+        journal.discard(&file).await?;
+        assert!(!file.exists());
+        assert_eq!(journal.rollback(mark).await, 0);
+        assert_eq!(fs::read_to_string(&file).await?, "stale");
+
+        journal.discard(&file).await?;
+        journal.commit().await;
+        assert!(!file.exists());
+        assert!(!dir.join("journal.backup").join("a.mp4.bak").exists());
+
+        fs::remove_dir_all(dir).await.ok();
+        Ok(())
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+/// size and modified time of a file and modified time of its sidecar,
+/// changed file or sidecar is treated as a new one
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Fingerprint {
+    size: u64,
+    modified: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sidecar: Option<u64>,
+}
+
+impl Fingerprint {
+    pub async fn of(file: &Path, sidecar: &Path) -> Result<Fingerprint> {
+        let metadata = fs::metadata(file)
+            .await
+            .with_context(|| format!("read metadata of {}", file.display()))?;
+        let sidecar = if sidecar.is_file() {
+            let metadata = fs::metadata(sidecar)
+                .await
+                .with_context(|| format!("read metadata of {}", sidecar.display()))?;
+            Some(Self::modified_of(&metadata)?)
+        } else {
+            None
+        };
+
+        Ok(Fingerprint {
+            size: metadata.len(),
+            modified: Self::modified_of(&metadata)?,
+            sidecar,
+        })
+    }
+
+    fn modified_of(metadata: &Metadata) -> Result<u64> {
+        let modified = metadata
+            .modified()
+            .with_context(|| "read modified time")?
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        Ok(modified)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    #[serde(rename = "succeed")]
+    Succeed,
+
+    #[serde(rename = "failed")]
+    Failed,
+
+    /// a conflicting video is left in input by `skip` or `keep-larger`
+    #[serde(rename = "kept")]
+    Kept,
+
+    /// not started before stop, or rolled back as not finished in time
+    #[serde(rename = "interrupted")]
+    Interrupted,
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Outcome::Succeed => "succeed",
+                Outcome::Failed => "failed",
+                Outcome::Kept => "kept",
+                Outcome::Interrupted => "interrupted",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Record {
+    #[serde(flatten)]
+    pub fingerprint: Fingerprint,
+    pub name: String,
+    pub outcome: Outcome,
+    pub output: Option<PathBuf>,
+}
+
+/// every file handled in previous runs, keyed by source path
+///
+/// changes are kept in memory until `save`, which is called once a run is done
+pub struct Ledger {
+    path: PathBuf,
+    records: HashMap<PathBuf, Record>,
+    changed: bool,
+}
+
+impl Ledger {
+    pub async fn load(path: impl Into<PathBuf>) -> Result<Ledger> {
+        let path = path.into();
+        let mut records: HashMap<PathBuf, Record> = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .await
+                .with_context(|| format!("read ledger {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("decode ledger {}", path.display()))?
+        } else {
+            HashMap::new()
+        };
+        // files moved or removed since then are never found again
+        let count = records.len();
+        records.retain(|file, _| file.exists());
+        let changed = records.len() != count;
+
+        Ok(Ledger {
+            path,
+            records,
+            changed,
+        })
+    }
+
+    /// record of the file, only if it succeeded and is not changed since then,
+    /// so that failed files are tried again
+    pub fn get(&self, file: &Path, fingerprint: &Fingerprint) -> Option<&Record> {
+        self.records.get(file).filter(|record| {
+            record.outcome == Outcome::Succeed && record.fingerprint == *fingerprint
+        })
+    }
+
+    pub fn record(&mut self, file: PathBuf, record: Record) {
+        self.records.insert(file, record);
+        self.changed = true;
+    }
+
+    pub fn forget(&mut self, file: &Path) {
+        self.changed |= self.records.remove(file).is_some();
+    }
+
+    pub async fn save(&mut self) -> Result<()> {
+        if !self.changed {
+            return Ok(());
+        }
+        let content = serde_json::to_string(&self.records).with_context(|| "encode ledger")?;
+        fs::write(&self.path, content)
+            .await
+            .with_context(|| format!("write ledger {}", self.path.display()))?;
+        self.changed = false;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn test_fingerprint_with_sidecar() -> Result<()> {
+        let dir = temp_dir("ledger");
+        let file = dir.join("a.mp4");
+        let sidecar = dir.join("a.javcap.toml");
+        fs::write(&file, "video").await?;
+
+        let mut ledger = Ledger::load(dir.join("ledger")).await?;
+        let fingerprint = Fingerprint::of(&file, &sidecar).await?;
+        ledger.record(
+            file.clone(),
+            Record {
+                fingerprint,
+                name: "A".to_string(),
+                outcome: Outcome::Succeed,
+                output: None,
+            },
+        );
+        assert!(ledger.get(&file, &fingerprint).is_some());
+
+        // a sidecar added later makes the file a new one
+        fs::write(&sidecar, "id = \"B\"").await?;
+        let fingerprint = Fingerprint::of(&file, &sidecar).await?;
+        assert!(ledger.get(&file, &fingerprint).is_none());
+
+        // records of earlier versions have no sidecar
+        let old = serde_json::from_str::<Fingerprint>(r#"{"size":5,"modified":1}"#)?;
+        assert_eq!(old.sidecar, None);
+
+        fs::remove_dir_all(dir).await.ok();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_and_missing() -> Result<()> {
+        let dir = temp_dir("ledger-missing");
+        let file = dir.join("a.mp4");
+        let gone = dir.join("b.mp4");
+        fs::write(&file, "video").await?;
+        fs::write(&gone, "video").await?;
+
+        let path = dir.join("ledger");
+        let mut ledger = Ledger::load(&path).await?;
+        let fingerprint = Fingerprint::of(&file, &dir.join("a.javcap.toml")).await?;
+        for file in [&file, &gone] {
+            ledger.record(
+                file.clone(),
+                Record {
+                    fingerprint,
+                    name: "A".to_string(),
+                    outcome: Outcome::Failed,
+                    output: None,
+                },
+            );
+        }
+        // failed files are tried again
+        assert!(ledger.get(&file, &fingerprint).is_none());
+        ledger.save().await?;
+
+        fs::remove_file(&gone).await?;
+        let ledger = Ledger::load(&path).await?;
+        assert!(ledger.records.contains_key(&file));
+        assert!(!ledger.records.contains_key(&gone));
+
+        fs::remove_dir_all(dir).await.ok();
+        Ok(())
+    }
+}
@@ -1,7 +1,34 @@
 mod app;
 mod bar;
+mod doctor;
+mod failure;
 mod helper;
+mod journal;
+mod ledger;
+mod library;
+mod logs;
 mod message;
 mod payload;
+mod picker;
+mod refresh;
+mod report;
+mod search;
+mod serve;
+mod shutdown;
+mod stats;
+#[cfg(test)]
+mod testing;
+mod webhook;
+mod wizard;
 
 pub use app::App;
+pub use doctor::doctor;
+pub use failure::Failure;
+pub use helper::cache_dir;
+pub use journal::{Journal, JournalKind};
+pub use logs::{LOG_NAME_FORMAT, LogFilter, log_dir, log_files, print_log, rotate_logs, video_id};
+pub use payload::Artifact;
+pub use search::search;
+pub use serve::serve;
+pub use stats::Stats;
+pub use wizard::init;
@@ -0,0 +1,143 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use tokio::fs;
+
+/// fields read back from a nfo in the library
+#[derive(Debug, Default)]
+pub struct NfoInfo {
+    pub id: String,
+    pub title: String,
+    pub plot: String,
+    pub rating: Option<f64>,
+    pub runtime: Option<u32>,
+    pub genres: Vec<String>,
+    pub country: String,
+    pub director: String,
+    pub premiered: String,
+    pub studio: String,
+    pub actors: Vec<String>,
+}
+
+impl NfoInfo {
+    /// read a nfo, fails if it is not well formed or has no id
+    pub async fn read(file: &Path) -> Result<NfoInfo> {
+        let content = fs::read_to_string(file)
+            .await
+            .with_context(|| format!("read {}", file.display()))?;
+        Self::parse(&content)
+    }
+
+    fn parse(content: &str) -> Result<NfoInfo> {
+        let mut reader = Reader::from_str(content);
+        let mut info = NfoInfo::default();
+        let mut in_movie = false;
+        let mut path = Vec::new();
+        loop {
+            match reader.read_event().with_context(|| "parse xml")? {
+                Event::Start(tag) => {
+                    let name = String::from_utf8_lossy(tag.name().as_ref()).to_string();
+                    if name == "movie" {
+                        in_movie = true;
+                    }
+                    path.push(name);
+                }
+                Event::End(_) => {
+                    path.pop();
+                }
+                Event::Text(text) if in_movie => {
+                    let text = text.unescape().with_context(|| "unescape text")?;
+                    let text = text.trim().to_string();
+                    let tags = path.iter().map(|tag| tag.as_str()).collect::<Vec<_>>();
+                    match tags.as_slice() {
+                        ["movie", "uniqueid"] => info.id = text,
+                        ["movie", "title"] => info.title = text,
+                        ["movie", "plot"] => info.plot = text,
+                        ["movie", "rating"] => info.rating = text.parse().ok(),
+                        ["movie", "runtime"] => info.runtime = text.parse().ok(),
+                        ["movie", "genre"] => info.genres.push(text),
+                        ["movie", "country"] => info.country = text,
+                        ["movie", "director"] => info.director = text,
+                        ["movie", "premiered"] => info.premiered = text,
+                        ["movie", "studio"] => info.studio = text,
+                        ["movie", "actor", "name"] => info.actors.push(text),
+                        _ => {}
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        if !in_movie {
+            bail!("no movie found");
+        }
+        if info.id.is_empty() {
+            bail!("no id found");
+        }
+
+        Ok(info)
+    }
+}
+
+/// all nfo files under the path
+pub async fn nfo_files(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut entries = fs::read_dir(path)
+        .await
+        .with_context(|| format!("read dir in {}", path.display()))?;
+    while let Some(entry) = entries.next_entry().await? {
+        let file = entry.path();
+        if file.is_dir() {
+            files.extend(Box::pin(nfo_files(&file)).await?);
+            continue;
+        }
+        if file.extension().is_some_and(|ext| ext == "nfo") {
+            files.push(file);
+        }
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nfo::{Country, Nfo};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let mut nfo = Nfo::builder().id("ABC-123").country(Country::Japan).build();
+        nfo.set_title("title & more".to_string());
+        nfo.set_plot("plot".to_string());
+        nfo.set_rating(8.5);
+        nfo.set_runtime(120);
+        nfo.genres_mut().insert("genre".to_string());
+        nfo.actors_mut().insert("actor".to_string());
+        nfo.set_director("director".to_string());
+        nfo.set_premiered("2024-01-02".to_string());
+        nfo.set_studio("studio".to_string());
+
+        let info = NfoInfo::parse(&nfo.to_string())?;
+        assert_eq!(info.id, "ABC-123");
+        assert_eq!(info.title, "title & more");
+        assert_eq!(info.plot, "plot");
+        assert_eq!(info.rating, Some(8.5));
+        assert_eq!(info.runtime, Some(120));
+        assert_eq!(info.genres, vec!["genre"]);
+        assert_eq!(info.country, "日本");
+        assert_eq!(info.director, "director");
+        assert_eq!(info.premiered, "2024-01-02");
+        assert_eq!(info.studio, "studio");
+        assert_eq!(info.actors, vec!["actor"]);
+
+        assert!(NfoInfo::parse("<movie><title>a</title></movie>").is_err());
+        assert!(NfoInfo::parse("<other><uniqueid>a</uniqueid></other>").is_err());
+        assert!(NfoInfo::parse("<movie><uniqueid>a</movie>").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,342 @@
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDateTime, TimeDelta};
+use log::{Level, LevelFilter};
+use tokio::fs::{self, File};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::time;
+
+use super::helper::cache_dir;
+
+tokio::task_local! {
+    static VIDEO_ID: String;
+}
+
+/// id of the video the current task works on
+pub fn video_id() -> Option<String> {
+    VIDEO_ID.try_with(|id| id.clone()).ok()
+}
+
+/// run the future with the video id attached to every log inside
+pub async fn with_video_id<F: Future>(id: String, f: F) -> F::Output {
+    VIDEO_ID.scope(id, f).await
+}
+
+/// log file of a run is named by its start time, like `20250101-120000.000.log`
+pub const LOG_NAME_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+
+/// log files of versions before logs of every run are kept
+const LEGACY_LOGS: [&str; 2] = ["log", "old.log"];
+
+pub fn log_dir() -> PathBuf {
+    cache_dir().join("logs")
+}
+
+/// log files of all runs, the latest first, other files in log dir are left alone
+pub async fn log_files() -> Result<Vec<PathBuf>> {
+    let log_dir = log_dir();
+    let mut files = Vec::new();
+    if !log_dir.exists() {
+        return Ok(files);
+    }
+
+    let mut entries = fs::read_dir(&log_dir)
+        .await
+        .with_context(|| format!("read dir in {}", log_dir.display()))?;
+    while let Some(entry) = entries.next_entry().await? {
+        let file = entry.path();
+        let is_run_log = file.extension().is_some_and(|ext| ext == "log")
+            && file
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| NaiveDateTime::parse_from_str(stem, LOG_NAME_FORMAT).is_ok());
+        if is_run_log {
+            files.push(file);
+        }
+    }
+    // file name starts with the time of the run
+    files.sort();
+    files.reverse();
+
+    Ok(files)
+}
+
+/// remove old logs so that only `keep` runs are left, including the new one
+pub async fn rotate_logs(keep: usize) -> Result<()> {
+    migrate_legacy_logs()
+        .await
+        .with_context(|| "migrate legacy logs")?;
+    let old = log_files().await?.into_iter().skip(keep.saturating_sub(1));
+    for file in old {
+        fs::remove_file(&file)
+            .await
+            .with_context(|| format!("remove {}", file.display()))?;
+    }
+
+    Ok(())
+}
+
+/// rename legacy logs by their modified time, so that they are rotated like logs of runs
+async fn migrate_legacy_logs() -> Result<()> {
+    let log_dir = log_dir();
+    let legacy = [cache_dir(), log_dir.clone()]
+        .into_iter()
+        .flat_map(|dir| LEGACY_LOGS.map(|name| dir.join(name)))
+        .filter(|file| file.is_file());
+    for file in legacy {
+        let modified = fs::metadata(&file)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .with_context(|| format!("read modified time of {}", file.display()))?;
+        let mut time = DateTime::<Local>::from(modified);
+        let target = loop {
+            let target = log_dir.join(format!("{}.log", time.format(LOG_NAME_FORMAT)));
+            if !target.exists() {
+                break target;
+            }
+            time += TimeDelta::milliseconds(1);
+        };
+        fs::rename(&file, &target)
+            .await
+            .with_context(|| format!("move {} to {}", file.display(), target.display()))?;
+    }
+
+    Ok(())
+}
+
+pub struct LogFilter {
+    id: Option<String>,
+    level: Option<LevelFilter>,
+}
+
+impl LogFilter {
+    pub fn new(id: Option<String>, level: Option<LevelFilter>) -> LogFilter {
+        LogFilter { id, level }
+    }
+
+    fn matches(&self, record: &str) -> bool {
+        let head = Self::head_of(record);
+        if let Some(level) = self.level
+            && head.as_ref().is_some_and(|(l, _)| *l > level)
+        {
+            return false;
+        }
+        if let Some(ref id) = self.id
+            && !head
+                .and_then(|(_, i)| i)
+                .is_some_and(|i| i.eq_ignore_ascii_case(id))
+        {
+            return false;
+        }
+
+        true
+    }
+
+    /// level and video id of the record, text record looks like `[time level target id] msg`,
+    /// json record has `level` and `id` fields
+    fn head_of(record: &str) -> Option<(Level, Option<String>)> {
+        if record.starts_with('{') {
+            let value = serde_json::from_str::<serde_json::Value>(record).ok()?;
+            let level = value.get("level")?.as_str()?.parse().ok()?;
+            let id = value
+                .get("id")
+                .and_then(|id| id.as_str())
+                .map(|id| id.to_string());
+            return Some((level, id));
+        }
+
+        let (head, _) = record.strip_prefix('[')?.split_once("] ")?;
+        let mut tokens = head.split_whitespace();
+        let level = Level::from_str(tokens.nth(1)?).ok()?;
+        // target
+        tokens.next()?;
+        let id = tokens.collect::<Vec<_>>().join(" ");
+        Some((level, (!id.is_empty()).then_some(id)))
+    }
+
+    fn is_start_of_record(line: &str) -> bool {
+        line.starts_with('{') || Self::head_of(line).is_some()
+    }
+
+    /// matched records in content, and the incomplete tail
+    fn filter(&self, content: &str, flush: bool) -> (Vec<String>, String) {
+        let mut matched = Vec::new();
+        let mut record = String::new();
+        let mut lines = content.split_inclusive('\n').peekable();
+        while let Some(line) = lines.next() {
+            if !line.ends_with('\n') && lines.peek().is_none() && !flush {
+                // half written line, wait for the rest
+                let mut tail = record;
+                tail.push_str(line);
+                return (matched, tail);
+            }
+            if Self::is_start_of_record(line) && !record.is_empty() {
+                if self.matches(&record) {
+                    matched.push(record.clone());
+                }
+                record.clear();
+            }
+            record.push_str(line);
+        }
+
+        if flush {
+            if !record.is_empty() && self.matches(&record) {
+                matched.push(record);
+            }
+            return (matched, String::new());
+        }
+        (matched, record)
+    }
+
+    /// print all matched records in content, return the incomplete tail
+    fn print(&self, content: &str, flush: bool) -> String {
+        let (matched, tail) = self.filter(content, flush);
+        for record in matched {
+            println!("{}", record.trim_end());
+        }
+
+        tail
+    }
+}
+
+pub async fn print_log(file: &Path, filter: &LogFilter, follow: bool) -> Result<()> {
+    let mut content = String::new();
+    let mut f = File::open(file)
+        .await
+        .with_context(|| format!("open {}", file.display()))?;
+    f.read_to_string(&mut content)
+        .await
+        .with_context(|| "read log file")?;
+    if !follow {
+        filter.print(&content, true);
+        return Ok(());
+    }
+
+    // a record is written at once, so it is complete if ends with new line
+    let mut tail = filter.print(&content, content.ends_with('\n'));
+    let mut pos = f.stream_position().await?;
+    loop {
+        time::sleep(Duration::from_millis(500)).await;
+        let len = fs::metadata(file)
+            .await
+            .with_context(|| format!("read metadata of {}", file.display()))?
+            .len();
+        if len <= pos {
+            continue;
+        }
+
+        f.seek(SeekFrom::Start(pos)).await?;
+        let mut buf = Vec::new();
+        f.read_to_end(&mut buf)
+            .await
+            .with_context(|| "read log file")?;
+        pos += buf.len() as u64;
+        tail.push_str(&String::from_utf8_lossy(&buf));
+        tail = filter.print(&tail, tail.ends_with('\n'));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const TEXT: &str = "\
+[2025-01-01T12:00:00 INFO  javcap::app] found videos: 2(ABP-12, ABP-123)
+[2025-01-01T12:00:01 INFO  spider ABP-123] found in javdb
+[2025-01-01T12:00:02 WARN  spider ABP-12] not found in airav
+[2025-01-01T12:00:03 ERROR javcap::app ABP-12] ABP-12 failed, caused by not found
+Caused by:
+    0: in finder javdb
+";
+
+    const JSON: &str = "\
+{\"time\":\"2025-01-01T12:00:00\",\"level\":\"INFO\",\"target\":\"javcap::app\",\"id\":null,\"msg\":\"found ABP-12\"}
+{\"time\":\"2025-01-01T12:00:01\",\"level\":\"INFO\",\"target\":\"spider\",\"id\":\"ABP-123\",\"msg\":\"found in javdb\"}
+{\"time\":\"2025-01-01T12:00:02\",\"level\":\"WARN\",\"target\":\"spider\",\"id\":\"ABP-12\",\"msg\":\"not found in airav\"}
+";
+
+    fn filter(content: &str, id: Option<&str>, level: Option<LevelFilter>) -> Vec<String> {
+        let (matched, tail) = LogFilter::new(id.map(String::from), level).filter(content, true);
+        assert_eq!(tail, "");
+        matched
+            .iter()
+            .map(|record| record.lines().next().unwrap_or_default().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_head_of() {
+        let cases = [
+            (
+                "[2025-01-01T12:00:00 INFO  javcap] start",
+                Some((Level::Info, None)),
+            ),
+            (
+                "[2025-01-01T12:00:00 WARN  spider FC2-PPV-123] msg",
+                Some((Level::Warn, Some("FC2-PPV-123"))),
+            ),
+            (
+                "[2025-01-01T12:00:00 DEBUG app some title] msg",
+                Some((Level::Debug, Some("some title"))),
+            ),
+            (
+                r#"{"level":"ERROR","id":"ABP-123"}"#,
+                Some((Level::Error, Some("ABP-123"))),
+            ),
+            ("    0: in finder javdb", None),
+            ("[not a header", None),
+        ];
+        for (record, expected) in cases {
+            let expected = expected.map(|(level, id)| (level, id.map(String::from)));
+            assert_eq!(LogFilter::head_of(record), expected, "{record}");
+        }
+    }
+
+    #[test]
+    fn test_filter_by_id() {
+        for content in [TEXT, JSON] {
+            let matched = filter(content, Some("abp-12"), None);
+            assert!(
+                matched.iter().all(|record| !record.contains("ABP-123")),
+                "{matched:?}"
+            );
+            assert!(!matched.is_empty());
+        }
+        assert_eq!(filter(TEXT, Some("ABP-12"), None).len(), 2);
+        assert_eq!(filter(JSON, Some("ABP-123"), None).len(), 1);
+    }
+
+    #[test]
+    fn test_filter_by_level() {
+        assert_eq!(
+            filter(TEXT, None, Some(LevelFilter::Warn)),
+            vec![
+                "[2025-01-01T12:00:02 WARN  spider ABP-12] not found in airav",
+                "[2025-01-01T12:00:03 ERROR javcap::app ABP-12] ABP-12 failed, caused by not found",
+            ]
+        );
+        assert_eq!(filter(JSON, None, Some(LevelFilter::Warn)).len(), 1);
+    }
+
+    #[test]
+    fn test_filter_tail() {
+        let log = LogFilter::new(None, None);
+        let content = format!("{TEXT}[2025-01-01T12:00:04 INFO  jav");
+        let (matched, tail) = log.filter(&content, false);
+        assert_eq!(matched.len(), 3);
+        // the last record may still have lines to come
+        assert!(tail.starts_with("[2025-01-01T12:00:03 ERROR"));
+        assert!(tail.ends_with("12:00:04 INFO  jav"));
+
+        let tail = format!("{tail}cap] done\n");
+        let (matched, tail) = log.filter(&tail, true);
+        assert_eq!(matched.len(), 2);
+        assert_eq!(matched[0].lines().count(), 3);
+        assert_eq!(tail, "");
+    }
+}
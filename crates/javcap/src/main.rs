@@ -1,16 +1,21 @@
 use std::env;
 use std::fs::OpenOptions;
-use std::io::Write;
+use std::io::{self, IsTerminal, Write};
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::process::ExitCode;
+use std::time::Duration;
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use chrono::Local;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::Colorize;
-use config::Config;
+use config::{Config, LogFormat, Logging, Override};
 use env_logger::{Builder, Target};
-use javcap::App;
+use javcap::{
+    App, Artifact, Failure, Journal, JournalKind, LOG_NAME_FORMAT, LogFilter, Stats, log_dir,
+    log_files, print_log, rotate_logs, video_id,
+};
 use log::{LevelFilter, error, info};
 use self_update::Status;
 use self_update::backends::github::Update;
@@ -32,32 +37,282 @@ enum Commands {
         /// 配置文件路径
         #[arg(short, long)]
         config: Option<String>,
+
+        /// 只打印将要执行的操作, 不写入或移动任何文件
+        #[arg(long)]
+        dry_run: bool,
+
+        /// 重新处理之前运行中已成功处理过的文件
+        #[arg(long)]
+        force: bool,
+
+        /// 保存每个影片的处理结果, 以 .csv 结尾时保存为 csv, 否则为 json
+        #[arg(long)]
+        report: Option<PathBuf>,
+
+        /// 覆盖配置中的值, 如 network.proxy=http://127.0.0.1:7890, 可以指定多次
+        #[arg(long, value_name = "KEY=VALUE")]
+        set: Vec<Override>,
+    },
+
+    /// 监听输入文件夹, 刮削新加入的文件
+    Watch {
+        /// 配置文件路径
+        #[arg(short, long)]
+        config: Option<String>,
+
+        /// 文件大小和修改时间保持不变多少秒后才开始处理
+        #[arg(short, long, default_value_t = 10)]
+        settle: u64,
+
+        /// 重新处理之前运行中已成功处理过的文件
+        #[arg(long)]
+        force: bool,
+
+        /// 保存每个影片的处理结果, 以 .csv 结尾时保存为 csv, 否则为 json
+        #[arg(long)]
+        report: Option<PathBuf>,
+    },
+
+    /// 重新刮削输出文件夹中已整理的影片, 不移动影片
+    Rescrape {
+        /// 配置文件路径
+        #[arg(short, long)]
+        config: Option<String>,
+
+        /// 只刷新指定的文件, 可以指定多次, 默认刷新全部
+        #[arg(short, long, value_enum)]
+        only: Vec<Artifact>,
+
+        /// 保存每个影片的处理结果, 以 .csv 结尾时保存为 csv, 否则为 json
+        #[arg(long)]
+        report: Option<PathBuf>,
+    },
+
+    /// 重新处理之前运行中失败且未再处理过的影片
+    Retry {
+        /// 配置文件路径
+        #[arg(short, long)]
+        config: Option<String>,
+
+        /// 不使用指定的搜索器, 可以指定多次
+        #[arg(short = 'x', long)]
+        exclude_finder: Vec<String>,
+
+        /// 保存每个影片的处理结果, 以 .csv 结尾时保存为 csv, 否则为 json
+        #[arg(long)]
+        report: Option<PathBuf>,
+    },
+
+    /// 检查输出文件夹中的影片是否完整
+    Doctor {
+        /// 配置文件路径
+        #[arg(short, long)]
+        config: Option<String>,
+
+        /// 删除空文件夹, 并为 nfo 完好的影片重新下载缺失或损坏的图片
+        #[arg(long)]
+        fix: bool,
     },
 
-    /// 显示默认配置
-    Config,
+    /// 统计输出文件夹中影片的元数据
+    Stats {
+        /// 配置文件路径
+        #[arg(short, long)]
+        config: Option<String>,
+
+        /// 输出格式
+        #[arg(short, long, value_enum, default_value_t = StatsFormat::Table)]
+        output: StatsFormat,
+
+        /// 表格中每项只显示数量最多的前几个
+        #[arg(short, long, default_value_t = 10)]
+        top: usize,
+    },
+
+    /// 搜索番号并显示结果, 不处理任何文件
+    Search {
+        /// 番号或名称, 如 xxx-123
+        name: String,
+
+        /// 配置文件路径
+        #[arg(short, long)]
+        config: Option<String>,
+
+        /// 只使用指定的搜索器, 可以指定多次
+        #[arg(short, long)]
+        finder: Vec<String>,
+
+        /// 翻译标题和简介
+        #[arg(short, long)]
+        translate: bool,
+
+        /// 输出格式
+        #[arg(short, long, value_enum, default_value_t = Format::Debug)]
+        output: Format,
+    },
+
+    /// 配置相关, 不带子命令时显示默认配置
+    Config {
+        #[command(subcommand)]
+        command: Option<ConfigCommands>,
+    },
+
+    /// 显示运行日志
+    Log {
+        /// 显示倒数第几次运行的日志, 0 为最近一次
+        #[arg(short, long, default_value_t = 0)]
+        run: usize,
+
+        /// 只显示该番号的日志, 不区分大小写
+        #[arg(short, long)]
+        id: Option<String>,
+
+        /// 只显示该级别及以上的日志, 如 warn
+        #[arg(short, long)]
+        level: Option<LevelFilter>,
+
+        /// 持续显示新写入的日志
+        #[arg(short, long)]
+        follow: bool,
+    },
+
+    /// 启动本地 HTTP 接口, 供其他程序触发和查看刮削
+    Serve {
+        /// 配置文件路径
+        #[arg(short, long)]
+        config: Option<String>,
+
+        /// 监听地址, 接口没有鉴权, 请勿暴露到公网
+        #[arg(short, long, default_value = "127.0.0.1:8686")]
+        listen: SocketAddr,
+    },
 
-    /// 显示上次运行的日志
-    Log,
+    /// 撤销上次运行对文件的改动
+    ///
+    /// 被覆盖或替换的文件备份在缓存目录中, 保留到同一命令下次运行产生改动时删除;
+    /// 影片成功后失败文件夹中残留的副本在运行结束时删除, 不能撤销
+    Undo {
+        /// 撤销哪个命令的改动, run 包括 watch, retry 和 serve
+        #[arg(short, long, value_enum, default_value_t = JournalKind::Run)]
+        journal: JournalKind,
+    },
 
     /// 更新程序
     Upgrade,
 }
 
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// 逐步询问并生成配置文件
+    Init {
+        /// 配置文件路径, 默认写入默认位置
+        #[arg(short, long)]
+        config: Option<String>,
+
+        /// 覆盖已存在的配置文件
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// 检查配置文件, 包括路径是否存在, 输出文件夹是否被排除等
+    Validate {
+        /// 配置文件路径
+        #[arg(short, long)]
+        config: Option<String>,
+    },
+
+    /// 显示配置, 密钥和密码会被隐藏
+    Show {
+        /// 配置文件路径
+        #[arg(short, long)]
+        config: Option<String>,
+
+        /// 显示实际生效的配置, 包括环境变量和 --set 的覆盖, 否则显示默认配置
+        #[arg(long)]
+        effective: bool,
+
+        /// 覆盖配置中的值, 如 network.proxy=http://127.0.0.1:7890, 可以指定多次
+        #[arg(long, value_name = "KEY=VALUE")]
+        set: Vec<Override>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Debug,
+    Nfo,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum StatsFormat {
+    Table,
+    Json,
+}
+
 #[tokio::main]
 async fn main() -> ExitCode {
     let cli = Cli::parse();
     match cli.command {
         Some(command) => match command {
-            Commands::Run { config } => run(config).await,
-            Commands::Config => {
-                println!("{}", Config::DEFAULT_CONFIG.trim_end());
-                ExitCode::SUCCESS
-            }
-            Commands::Log => log().await,
+            Commands::Run {
+                config,
+                dry_run,
+                force,
+                report,
+                set,
+            } => run(config, dry_run, force, report, set).await,
+            Commands::Watch {
+                config,
+                settle,
+                force,
+                report,
+            } => watch(config, settle, force, report).await,
+            Commands::Rescrape {
+                config,
+                only,
+                report,
+            } => rescrape(config, only, report).await,
+            Commands::Retry {
+                config,
+                exclude_finder,
+                report,
+            } => retry(config, exclude_finder, report).await,
+            Commands::Doctor { config, fix } => doctor(config, fix).await,
+            Commands::Stats {
+                config,
+                output,
+                top,
+            } => stats(config, output, top).await,
+            Commands::Search {
+                name,
+                config,
+                finder,
+                translate,
+                output,
+            } => search(name, config, finder, translate, output).await,
+            Commands::Config { command } => match command {
+                Some(ConfigCommands::Init { config, force }) => config_init(config, force).await,
+                Some(ConfigCommands::Validate { config }) => config_validate(config).await,
+                Some(ConfigCommands::Show {
+                    config,
+                    effective,
+                    set,
+                }) => config_show(config, effective, set).await,
+                None => config_show(None, false, Vec::new()).await,
+            },
+            Commands::Log {
+                run,
+                id,
+                level,
+                follow,
+            } => log(run, id, level, follow).await,
+            Commands::Serve { config, listen } => serve(config, listen).await,
+            Commands::Undo { journal } => undo(journal).await,
             Commands::Upgrade => upgrade().await,
         },
-        None => run(None).await,
+        None => run(None, false, false, None, Vec::new()).await,
     }
 }
 
@@ -83,23 +338,143 @@ async fn _upgrade() -> Result<()> {
     Ok(())
 }
 
-async fn log() -> ExitCode {
-    let log_dir = log_dir();
-    let log_file = log_dir.join("log");
+async fn log(run: usize, id: Option<String>, level: Option<LevelFilter>, follow: bool) -> ExitCode {
+    match _log(run, id, level, follow).await {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
 
-    if !log_file.exists() {
-        eprintln!("no log file found");
-        return ExitCode::FAILURE;
+async fn _log(
+    run: usize,
+    id: Option<String>,
+    level: Option<LevelFilter>,
+    follow: bool,
+) -> Result<()> {
+    let files = log_files().await.with_context(|| "list log files")?;
+    let Some(file) = files.get(run) else {
+        bail!(
+            "no log file found for run {run}, {} run(s) kept",
+            files.len()
+        );
+    };
+
+    let filter = LogFilter::new(id, level);
+    print_log(file, &filter, follow).await
+}
+
+async fn search(
+    name: String,
+    config: Option<String>,
+    finders: Vec<String>,
+    translate: bool,
+    output: Format,
+) -> ExitCode {
+    match _search(name, config, finders, translate, output).await {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e:?}");
+            ExitCode::FAILURE
+        }
     }
+}
+
+async fn _search(
+    name: String,
+    config: Option<String>,
+    finders: Vec<String>,
+    translate: bool,
+    output: Format,
+) -> Result<()> {
+    let config = load_config(config, &[]).await?;
+    let (ty, found) = javcap::search(&config, &name, &finders, translate).await?;
 
-    match fs::read_to_string(&log_file)
+    for finder in found.succeed.iter() {
+        eprintln!("{} {finder}", "found in".green());
+    }
+    for (finder, err) in found.failed.iter() {
+        eprintln!("{} {finder}\n{}", "failed in".red(), err.red());
+    }
+    let Some(nfo) = found.nfo else {
+        bail!("could not find anything about {ty} in all finders");
+    };
+    if let Err(e) = nfo.validate() {
+        eprintln!("{}", format!("incomplete nfo: {e}").yellow());
+    }
+
+    match output {
+        Format::Debug => println!("{nfo:?}"),
+        Format::Nfo => println!("{nfo}"),
+        Format::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&nfo).with_context(|| "encode nfo to json")?
+        ),
+    }
+
+    Ok(())
+}
+
+async fn config_init(config: Option<String>, force: bool) -> ExitCode {
+    let path = config
+        .map(PathBuf::from)
+        .unwrap_or_else(Config::default_file);
+    match javcap::init(path, force).await {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn config_validate(config: Option<String>) -> ExitCode {
+    match _config_validate(config).await {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn _config_validate(config: Option<String>) -> Result<bool> {
+    // never generate a default config here, it would hide the real problem
+    let path = config
+        .map(PathBuf::from)
+        .unwrap_or_else(Config::default_file);
+    let config = Config::load_from(&path)
         .await
-        .with_context(|| "read log file")
-    {
-        Ok(content) => {
-            println!("{}", content.trim_end());
-            ExitCode::SUCCESS
+        .with_context(|| "load config")?
+        .apply(&Override::from_env())
+        .with_context(|| "apply overrides")?;
+
+    let mut ok = true;
+    if let Err(e) = config.validate() {
+        ok = false;
+        eprintln!("{}", format!("error: {e}").red());
+    }
+    for issue in config.lint() {
+        if issue.is_error() {
+            ok = false;
+            eprintln!("{}", issue.to_string().red());
+        } else {
+            eprintln!("{}", issue.to_string().yellow());
         }
+    }
+    if ok {
+        println!("{} {}", "config is valid".green(), path.display());
+    }
+
+    Ok(ok)
+}
+
+async fn config_show(config: Option<String>, effective: bool, sets: Vec<Override>) -> ExitCode {
+    match _config_show(config, effective, sets).await {
+        Ok(_) => ExitCode::SUCCESS,
         Err(e) => {
             eprintln!("{e:?}");
             ExitCode::FAILURE
@@ -107,7 +482,139 @@ async fn log() -> ExitCode {
     }
 }
 
-async fn run(config: Option<String>) -> ExitCode {
+async fn _config_show(config: Option<String>, effective: bool, sets: Vec<Override>) -> Result<()> {
+    if !effective {
+        println!("{}", Config::DEFAULT_CONFIG.trim_end());
+        return Ok(());
+    }
+
+    let config = load_config(config, &sets).await?;
+    let content = toml::to_string(&config.redacted()).with_context(|| "encode config")?;
+    println!("{}", content.trim_end());
+
+    Ok(())
+}
+
+async fn doctor(config: Option<String>, fix: bool) -> ExitCode {
+    match _doctor(config, fix).await {
+        Ok(0) => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn _doctor(config: Option<String>, fix: bool) -> Result<usize> {
+    let config = load_config(config, &[]).await?;
+    init_logger(&config.log, Some("doctor"))
+        .await
+        .with_context(|| "init logger")?;
+    info!("app version: v{}({})", app::VERSION, app::HASH);
+
+    javcap::doctor(&config, fix).await
+}
+
+async fn stats(config: Option<String>, output: StatsFormat, top: usize) -> ExitCode {
+    match _stats(config, output, top).await {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn _stats(config: Option<String>, output: StatsFormat, top: usize) -> Result<()> {
+    let config = load_config(config, &[]).await?;
+    init_logger(&config.log, Some("stats"))
+        .await
+        .with_context(|| "init logger")?;
+    info!("app version: v{}({})", app::VERSION, app::HASH);
+
+    let stats = Stats::collect(&config).await?;
+    match output {
+        StatsFormat::Table => stats.print_table(top),
+        StatsFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&stats).with_context(|| "encode stats to json")?
+        ),
+    }
+
+    Ok(())
+}
+
+async fn serve(config: Option<String>, listen: SocketAddr) -> ExitCode {
+    with_banner(_serve(config, listen)).await
+}
+
+async fn _serve(config: Option<String>, listen: SocketAddr) -> Result<()> {
+    let config = load_config(config, &[]).await?;
+    init_logger(&config.log, None)
+        .await
+        .with_context(|| "init logger")?;
+    info!("app version: v{}({})", app::VERSION, app::HASH);
+
+    javcap::serve(config, listen).await
+}
+
+async fn undo(kind: JournalKind) -> ExitCode {
+    let journal = kind.path();
+    if !journal.exists() {
+        eprintln!("no journal found");
+        return ExitCode::FAILURE;
+    }
+
+    match Journal::undo(&journal).await {
+        Ok(0) => ExitCode::SUCCESS,
+        Ok(failed) => {
+            eprintln!("{failed} change(s) could not be undone, journal kept");
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("{e:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(
+    config: Option<String>,
+    dry_run: bool,
+    force: bool,
+    report: Option<PathBuf>,
+    sets: Vec<Override>,
+) -> ExitCode {
+    with_banner(_run(config, dry_run, force, report, sets)).await
+}
+
+async fn watch(
+    config: Option<String>,
+    settle: u64,
+    force: bool,
+    report: Option<PathBuf>,
+) -> ExitCode {
+    with_banner(_watch(config, settle, force, report)).await
+}
+
+async fn rescrape(
+    config: Option<String>,
+    only: Vec<Artifact>,
+    report: Option<PathBuf>,
+) -> ExitCode {
+    with_banner(_rescrape(config, only, report)).await
+}
+
+async fn retry(
+    config: Option<String>,
+    exclude_finders: Vec<String>,
+    report: Option<PathBuf>,
+) -> ExitCode {
+    with_banner(_retry(config, exclude_finders, report)).await
+}
+
+async fn with_banner(task: impl Future<Output = Result<()>>) -> ExitCode {
     println!("{}", ">".repeat(*app::LINE_LENGTH).yellow());
     let banner = include_str!("../banner");
     for line in banner.lines() {
@@ -135,7 +642,7 @@ async fn run(config: Option<String>) -> ExitCode {
         width = app::LINE_LENGTH
     );
     println!();
-    let code = match _run(config).await {
+    let code = match task.await {
         Ok(_) => ExitCode::SUCCESS,
         Err(e) => {
             eprintln!("{:#^width$}", " Error ".red(), width = app::LINE_LENGTH);
@@ -148,19 +655,122 @@ async fn run(config: Option<String>) -> ExitCode {
     code
 }
 
-async fn _run(config: Option<String>) -> Result<()> {
-    init_logger().await.with_context(|| "init logger")?;
-    info!("app version: v{}({})", app::VERSION, app::HASH);
-
+/// env overrides are applied before the ones given in command line
+async fn load_config(config: Option<String>, sets: &[Override]) -> Result<Config> {
     let config = match config {
         Some(path) => Config::load_from(path)
             .await
             .with_context(|| "load config")?,
         None => Config::load().await.with_context(|| "load config")?,
     };
+    let mut overrides = Override::from_env();
+    overrides.extend_from_slice(sets);
+    let config = config
+        .apply(&overrides)
+        .with_context(|| "apply overrides")?;
     config.validate().with_context(|| "validate config")?;
 
-    if config.check_for_update {
+    Ok(config)
+}
+
+async fn _watch(
+    config: Option<String>,
+    settle: u64,
+    force: bool,
+    report: Option<PathBuf>,
+) -> Result<()> {
+    let config = load_config(config, &[]).await?;
+    init_logger(&config.log, None)
+        .await
+        .with_context(|| "init logger")?;
+    info!("app version: v{}({})", app::VERSION, app::HASH);
+    let app = App::builder()
+        .config(config)
+        .force(force)
+        .maybe_report(report)
+        .build()
+        .await
+        .with_context(|| "init app")?;
+
+    app.watch(Duration::from_secs(settle))
+        .await
+        .with_context(|| "watch app")
+}
+
+async fn _rescrape(
+    config: Option<String>,
+    only: Vec<Artifact>,
+    report: Option<PathBuf>,
+) -> Result<()> {
+    let config = load_config(config, &[]).await?;
+    init_logger(&config.log, None)
+        .await
+        .with_context(|| "init logger")?;
+    info!("app version: v{}({})", app::VERSION, app::HASH);
+    let app = App::builder()
+        .config(config)
+        .maybe_report(report)
+        .journal(JournalKind::Rescrape)
+        .build()
+        .await
+        .with_context(|| "init app")?;
+
+    let only = if only.is_empty() {
+        Artifact::ALL.to_vec()
+    } else {
+        only
+    };
+    app.rescrape(only).await.with_context(|| "rescrape app")
+}
+
+async fn _retry(
+    config: Option<String>,
+    exclude_finders: Vec<String>,
+    report: Option<PathBuf>,
+) -> Result<()> {
+    let config = load_config(config, &[]).await?;
+    init_logger(&config.log, None)
+        .await
+        .with_context(|| "init logger")?;
+    info!("app version: v{}({})", app::VERSION, app::HASH);
+
+    let failures = Failure::load_all().await.with_context(|| "load failures")?;
+    if failures.is_empty() {
+        info!("nothing failed in previous runs");
+        println!("nothing failed in previous runs");
+        return Ok(());
+    }
+
+    let interactive = io::stdin().is_terminal() && io::stdout().is_terminal();
+    let app = App::builder()
+        .config(config)
+        .interactive(interactive)
+        .exclude_finders(exclude_finders)
+        .maybe_report(report)
+        .build()
+        .await
+        .with_context(|| "init app")?;
+
+    app.retry(failures).await.with_context(|| "retry app")
+}
+
+async fn _run(
+    config: Option<String>,
+    dry_run: bool,
+    force: bool,
+    report: Option<PathBuf>,
+    sets: Vec<Override>,
+) -> Result<()> {
+    let config = load_config(config, &sets).await?;
+    init_logger(&config.log, None)
+        .await
+        .with_context(|| "init logger")?;
+    info!("app version: v{}({})", app::VERSION, app::HASH);
+
+    if dry_run {
+        info!("dry run, nothing will be written or moved");
+        println!("{}", "dry run, nothing will be written or moved".yellow());
+    } else if config.check_for_update {
         info!("check for update...");
         println!("check for update...");
         let status = tokio::task::spawn_blocking(check_for_update).await??;
@@ -174,7 +784,16 @@ async fn _run(config: Option<String>) -> Result<()> {
         println!("latest version, skip");
     }
 
-    let app = App::new(config).await.with_context(|| "init app")?;
+    let interactive = io::stdin().is_terminal() && io::stdout().is_terminal();
+    let app = App::builder()
+        .config(config)
+        .dry_run(dry_run)
+        .interactive(interactive)
+        .force(force)
+        .maybe_report(report)
+        .build()
+        .await
+        .with_context(|| "init app")?;
 
     app.run().await.with_context(|| "run app")
 }
@@ -196,32 +815,24 @@ fn check_for_update() -> Result<Status> {
     Ok(status)
 }
 
-fn log_dir() -> PathBuf {
-    let username = whoami::username();
-    #[cfg(target_os = "macos")]
-    let user_dir = PathBuf::from("/Users").join(username);
-    #[cfg(target_os = "linux")]
-    let user_dir = PathBuf::from("/home").join(username);
-    #[cfg(target_os = "windows")]
-    let user_dir = PathBuf::from("C:\\Users").join(username);
-
-    user_dir.join(".cache").join(app::NAME)
-}
-
-async fn init_logger() -> Result<()> {
+/// a command with a name logs to `<name>.log`, replaced every time, so that only runs
+/// are kept and rotated
+async fn init_logger(logging: &Logging, name: Option<&str>) -> Result<()> {
     let log_dir = log_dir();
     if !log_dir.exists() {
         fs::create_dir_all(&log_dir)
             .await
             .with_context(|| format!("create dir {}", log_dir.display()))?;
     }
-    let log_file = log_dir.join("log");
-    if log_file.exists() {
-        let to = log_dir.join("old.log");
-        fs::rename(&log_file, &to)
-            .await
-            .with_context(|| format!("rename {} to {}", log_file.display(), to.display()))?;
-    }
+    let log_file = match name {
+        Some(name) => log_dir.join(format!("{name}.log")),
+        None => {
+            rotate_logs(logging.keep)
+                .await
+                .with_context(|| "rotate logs")?;
+            log_dir.join(format!("{}.log", Local::now().format(LOG_NAME_FORMAT)))
+        }
+    };
     let log_file = OpenOptions::new()
         .create(true)
         .truncate(true)
@@ -241,16 +852,31 @@ async fn init_logger() -> Result<()> {
     } else {
         Builder::from_env(LOG_ENV_KEY)
     };
+    let format = logging.format;
     logger
-        .format(|buf, record| {
-            writeln!(
-                buf,
-                "[{} {:<5} {}] {}",
-                Local::now().format("%Y-%m-%dT%H:%M:%S"),
-                record.level(),
-                record.target(),
-                record.args(),
-            )
+        .format(move |buf, record| {
+            let time = Local::now().format("%Y-%m-%dT%H:%M:%S");
+            let id = video_id();
+            match format {
+                LogFormat::Text => writeln!(
+                    buf,
+                    "[{time} {:<5} {}{}] {}",
+                    record.level(),
+                    record.target(),
+                    id.map(|id| format!(" {id}")).unwrap_or_default(),
+                    record.args(),
+                ),
+                LogFormat::Json => {
+                    let line = serde_json::json!({
+                        "time": time.to_string(),
+                        "level": record.level().as_str(),
+                        "target": record.target(),
+                        "id": id,
+                        "msg": record.args().to_string(),
+                    });
+                    writeln!(buf, "{line}")
+                }
+            }
         })
         .target(Target::Pipe(Box::new(log_file)))
         .init();
@@ -1,17 +1,39 @@
 use std::fmt::{self, Display};
 
+use video::Video;
+
 use super::payload::Payload;
+use super::report::Trace;
 
 pub enum Message {
-    Loaded(Box<Payload>),
-    Failed(String, String),
+    Loaded(Box<Payload>, Trace),
+    Failed(Box<Video>, String, Trace),
+    Skipped(Box<Video>, Trace),
+}
+
+impl Message {
+    pub fn video(&self) -> &Video {
+        match self {
+            Message::Loaded(payload, _) => payload.video(),
+            Message::Failed(video, _, _) | Message::Skipped(video, _) => video,
+        }
+    }
+
+    pub fn trace(&self) -> &Trace {
+        match self {
+            Message::Loaded(_, trace)
+            | Message::Failed(_, _, trace)
+            | Message::Skipped(_, trace) => trace,
+        }
+    }
 }
 
 impl Display for Message {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Message::Loaded(payload) => write!(f, "{}", payload.video().ty()),
-            Message::Failed(name, _) => write!(f, "{name}"),
+            Message::Loaded(payload, _) => write!(f, "{}", payload.video().ty()),
+            Message::Failed(video, _, _) => write!(f, "{}", video.ty()),
+            Message::Skipped(video, _) => write!(f, "{}", video.ty()),
         }
     }
 }
@@ -1,18 +1,53 @@
-use std::path::Path;
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use bon::bon;
+use clap::ValueEnum;
 use colored::Colorize;
-use config::Tag;
+use config::{Conflict, Mode, Tag};
 use getset::Getters;
 use log::info;
 use nfo::Nfo;
 use tokio::fs::{self, OpenOptions};
 use tokio::io::AsyncWriteExt;
-use video::{Video, VideoType};
+use video::{Video, VideoFile, VideoType};
 
 use super::bar::Bar;
+use super::journal::{self, Journal};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Artifact {
+    Nfo,
+    Poster,
+    Fanart,
+    Subtitle,
+}
+
+impl Display for Artifact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Artifact::Nfo => "nfo",
+                Artifact::Poster => "poster",
+                Artifact::Fanart => "fanart",
+                Artifact::Subtitle => "subtitle",
+            }
+        )
+    }
+}
+
+impl Artifact {
+    pub const ALL: [Artifact; 4] = [
+        Artifact::Nfo,
+        Artifact::Poster,
+        Artifact::Fanart,
+        Artifact::Subtitle,
+    ];
+}
 
 #[derive(Getters)]
 pub struct Payload {
@@ -20,108 +55,266 @@ pub struct Payload {
     video: Video,
     nfo: Nfo,
     bar: Arc<Bar>,
+    journal: Arc<Journal>,
+    /// base of all file names, the video name unless renamed for conflicts
+    name: String,
 }
 
 #[bon]
 impl Payload {
     #[builder]
-    pub fn new(video: Video, nfo: Nfo, bar: Arc<Bar>) -> Payload {
-        Payload { video, nfo, bar }
+    pub fn new(video: Video, nfo: Nfo, bar: Arc<Bar>, journal: Arc<Journal>) -> Payload {
+        let name = video.ty().to_string();
+        Payload {
+            video,
+            nfo,
+            bar,
+            journal,
+            name,
+        }
     }
 
-    async fn write_fanart_to(&self, path: &Path) -> Result<()> {
-        let name = self.video.ty();
-        let filename = format!("{name}-fanart.jpg");
-        let file = path.join(filename);
-        Self::write_to_file(self.nfo.fanart(), &file)
-            .await
-            .with_context(|| format!("write to file {}", file.display()))?;
-        info!("write fanart of {name} to {}", file.display());
-        self.bar.message(format!("fanart ... {}", "ok".green()));
+    /// with `rename`, take the first name like `xxx-123 (1)` that none of the files has in path,
+    /// so that videos, nfo and images keep sharing one name
+    pub async fn rename_in(&mut self, path: &Path, conflict: Conflict) {
+        if conflict != Conflict::Rename {
+            return;
+        }
 
-        Ok(())
+        let base = self.video.ty().to_string();
+        let is_free = |name: &str| {
+            self.filenames(name)
+                .iter()
+                .all(|file| !path.join(file).exists())
+        };
+        let name = (0..)
+            .map(|idx| match idx {
+                0 => base.clone(),
+                idx => format!("{base} ({idx})"),
+            })
+            .find(|name| is_free(name))
+            .unwrap_or(base);
+        if name != self.name {
+            info!("rename {} to {name} by {conflict}", self.video.ty());
+        }
+        self.name = name;
     }
 
-    async fn write_poster_to(&self, path: &Path) -> Result<()> {
-        let name = self.video.ty();
-        let filename = format!("{name}-poster.jpg");
-        let file = path.join(filename);
-        Self::write_to_file(self.nfo.poster(), &file)
-            .await
-            .with_context(|| format!("write to file {}", file.display()))?;
-        info!("write poster of {name} to {}", file.display());
-        self.bar.message(format!("poster ... {}", "ok".green()));
+    /// names of all files of the payload, if it is named by name
+    fn filenames(&self, name: &str) -> Vec<String> {
+        let mut files = vec![
+            format!("{name}-fanart.jpg"),
+            format!("{name}-poster.jpg"),
+            format!("{name}.nfo"),
+            format!("{name}.srt"),
+        ];
+        files.extend(
+            self.video
+                .files()
+                .iter()
+                .map(|video| Self::video_filename_of(name, video)),
+        );
 
-        Ok(())
+        files
     }
 
-    async fn write_to_file(bytes: &[u8], file: &Path) -> Result<()> {
-        OpenOptions::new()
-            .create(true)
-            .truncate(true)
-            .write(true)
-            .open(file)
-            .await
-            .with_context(|| format!("open {}", file.display()))?
-            .write_all(bytes)
+    fn fanart_filename(&self) -> String {
+        format!("{}-fanart.jpg", self.name)
+    }
+
+    fn poster_filename(&self) -> String {
+        format!("{}-poster.jpg", self.name)
+    }
+
+    fn nfo_filename(&self) -> String {
+        format!("{}.nfo", self.name)
+    }
+
+    fn subtitle_filename(&self) -> String {
+        format!("{}.srt", self.name)
+    }
+
+    fn video_filename(&self, video: &VideoFile) -> String {
+        Self::video_filename_of(&self.name, video)
+    }
+
+    fn video_filename_of(name: &str, video: &VideoFile) -> String {
+        let idx = video.idx();
+        if *idx == 0 {
+            format!("{name}.{}", video.ext())
+        } else {
+            format!("{name}-CD{idx}.{}", video.ext())
+        }
+    }
+
+    async fn write_fanart_to(&self, path: &Path, conflict: Conflict) -> Result<()> {
+        let file = path.join(self.fanart_filename());
+        self.write_artifact_to("fanart", self.nfo.fanart(), &file, conflict)
             .await
-            .with_context(|| "write content")?;
+    }
 
-        Ok(())
+    async fn write_poster_to(&self, path: &Path, conflict: Conflict) -> Result<()> {
+        let file = path.join(self.poster_filename());
+        self.write_artifact_to("poster", self.nfo.poster(), &file, conflict)
+            .await
     }
 
-    async fn write_nfo_to(&self, path: &Path) -> Result<()> {
-        let name = self.video.ty();
-        let filename = format!("{name}.nfo");
-        let file = path.join(filename);
+    async fn write_nfo_to(&self, path: &Path, conflict: Conflict) -> Result<()> {
+        let file = path.join(self.nfo_filename());
         let nfo = self.nfo.to_string();
-        Self::write_to_file(nfo.as_bytes(), &file)
+        self.write_artifact_to("nfo", nfo.as_bytes(), &file, conflict)
             .await
-            .with_context(|| format!("write to file {}", file.display()))?;
-        info!("write nfo of {name} to {}", file.display());
-        self.bar.message(format!("nfo ... {}", "ok".green()));
-
-        Ok(())
     }
 
-    async fn write_subtitle_to(&self, path: &Path) -> Result<()> {
+    async fn write_subtitle_to(&self, path: &Path, conflict: Conflict) -> Result<()> {
         if self.nfo.subtitle().is_empty() {
             self.bar.message(format!("subtitle ... {}", "no".red()));
             return Ok(());
         }
 
+        let file = path.join(self.subtitle_filename());
+        self.write_artifact_to("subtitle", self.nfo.subtitle(), &file, conflict)
+            .await
+    }
+
+    async fn write_artifact_to(
+        &self,
+        kind: &str,
+        bytes: &[u8],
+        file: &Path,
+        conflict: Conflict,
+    ) -> Result<()> {
         let name = self.video.ty();
-        let filename = format!("{name}.srt");
-        let file = path.join(filename);
-        Self::write_to_file(self.nfo.subtitle(), &file)
+        let Some(file) = Self::resolve(file, bytes.len() as u64, conflict)
+            .await
+            .with_context(|| format!("resolve conflict of {}", file.display()))?
+        else {
+            info!("{kind} of {name} exists, skip by {conflict}");
+            self.bar.message(format!("{kind} ... {}", "skip".yellow()));
+            return Ok(());
+        };
+
+        self.write_to_file(bytes, &file)
             .await
             .with_context(|| format!("write to file {}", file.display()))?;
-        info!("write subtitle of {name} to {}", file.display());
-        self.bar.message(format!("subtitle ... {}", "ok".green()));
+        info!("write {kind} of {name} to {}", file.display());
+        self.bar.message(format!("{kind} ... {}", "ok".green()));
 
         Ok(())
     }
 
-    pub async fn move_videos_to(&self, path: &Path) -> Result<()> {
+    async fn write_to_file(&self, bytes: &[u8], file: &Path) -> Result<()> {
+        // write to a temp file beside, so that the old file is kept if failed
+        let name = file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        let temp = file.with_file_name(format!(".{name}.tmp"));
+        if let Err(e) = Self::write_and_sync(bytes, &temp).await {
+            fs::remove_file(&temp).await.ok();
+            return Err(e);
+        }
+
+        // the old file is put back by undo
+        if file.exists()
+            && let Err(e) = self.journal.backup(file).await
+        {
+            fs::remove_file(&temp).await.ok();
+            return Err(e);
+        }
+        if let Err(e) = self.journal.write_file(file).await {
+            fs::remove_file(&temp).await.ok();
+            return Err(e).with_context(|| format!("record file {}", file.display()));
+        }
+        if let Err(e) = fs::rename(&temp, file).await {
+            fs::remove_file(&temp).await.ok();
+            return Err(e)
+                .with_context(|| format!("rename {} to {}", temp.display(), file.display()));
+        }
+
+        Ok(())
+    }
+
+    async fn write_and_sync(bytes: &[u8], file: &Path) -> Result<()> {
+        let mut f = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(file)
+            .await
+            .with_context(|| format!("open {}", file.display()))?;
+        f.write_all(bytes).await.with_context(|| "write content")?;
+        f.sync_all().await.with_context(|| "sync content")?;
+
+        Ok(())
+    }
+
+    /// where to write a file of given size by the conflict policy, `None` to skip
+    ///
+    /// keep-larger compares size only, a file of the same size is taken as the same one
+    async fn resolve(file: &Path, size: u64, conflict: Conflict) -> Result<Option<PathBuf>> {
+        if !file.exists() {
+            return Ok(Some(file.to_path_buf()));
+        }
+
+        match conflict {
+            Conflict::Skip => Ok(None),
+            Conflict::Overwrite => Ok(Some(file.to_path_buf())),
+            // the payload is renamed as a whole by `rename_in`, so whatever is there is kept
+            Conflict::Rename => Ok(None),
+            Conflict::KeepLarger => {
+                let existed = fs::metadata(file)
+                    .await
+                    .with_context(|| format!("read metadata of {}", file.display()))?
+                    .len();
+                if size > existed {
+                    Ok(Some(file.to_path_buf()))
+                } else {
+                    Ok(None)
+                }
+            }
+        }
+    }
+
+    /// return files kept in place by the conflict policy
+    pub async fn move_videos_to(
+        &self,
+        path: &Path,
+        mode: Mode,
+        conflict: Conflict,
+    ) -> Result<Vec<PathBuf>> {
         let name = self.video.ty();
+        let mut kept = Vec::new();
         for video in self.video.files() {
             let idx = video.idx();
-            let filename = if *idx == 0 {
-                format!("{name}.{}", video.ext())
-            } else {
-                format!("{name}-CD{idx}.{}", video.ext())
-            };
-            let out = path.join(&filename);
-            if out.exists() {
-                info!("video already exists {}", out.display());
-                self.bar
-                    .message(format!("video already exists {}", out.display()));
-                continue;
-            }
             let src = video.location();
-            fs::rename(src, &out).await?;
+            let size = fs::metadata(src)
+                .await
+                .with_context(|| format!("read metadata of {}", src.display()))?
+                .len();
+            let target = path.join(self.video_filename(video));
+            let Some(out) = Self::resolve(&target, size, conflict)
+                .await
+                .with_context(|| format!("resolve conflict of {}", target.display()))?
+            else {
+                info!(
+                    "video already exists {}, {} is kept by {conflict}",
+                    target.display(),
+                    src.display()
+                );
+                self.bar.message(format!(
+                    "video already exists {}, {} {}",
+                    target.display(),
+                    "kept in".yellow(),
+                    src.display()
+                ));
+                kept.push(src.clone());
+                continue;
+            };
+
+            Self::place(&self.journal, src, &out, mode).await?;
             info!(
-                "move video of {name} from {} to {}",
+                "{mode} video of {name} from {} to {}",
                 src.display(),
                 out.display()
             );
@@ -133,6 +326,69 @@ impl Payload {
             self.bar.message(msg);
         }
 
+        Ok(kept)
+    }
+
+    /// put the file to out by mode, an existing out is replaced only after the new one is ready
+    pub async fn place(journal: &Journal, src: &Path, out: &Path, mode: Mode) -> Result<()> {
+        if !out.exists() {
+            return Self::record_and_transfer(journal, src, out, mode).await;
+        }
+
+        // transfer to a temp file beside, so that the old file is kept if failed
+        let name = out
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        let temp = out.with_file_name(format!(".{name}.tmp"));
+        if let Err(e) = Self::record_and_transfer(journal, src, &temp, mode).await {
+            if mode != Mode::Move {
+                fs::remove_file(&temp).await.ok();
+            }
+            return Err(e);
+        }
+        // the old file is put back by undo
+        journal.backup(out).await?;
+        journal
+            .move_file(&temp, out)
+            .await
+            .with_context(|| format!("record file {}", out.display()))?;
+        fs::rename(&temp, out)
+            .await
+            .with_context(|| format!("rename {} to {}", temp.display(), out.display()))?;
+
+        Ok(())
+    }
+
+    async fn record_and_transfer(
+        journal: &Journal,
+        src: &Path,
+        out: &Path,
+        mode: Mode,
+    ) -> Result<()> {
+        match mode {
+            Mode::Move => journal.move_file(src, out).await,
+            Mode::Copy | Mode::Hardlink | Mode::Symlink => journal.write_file(out).await,
+        }
+        .with_context(|| format!("record file {}", out.display()))?;
+        Self::transfer(src, out, mode)
+            .await
+            .with_context(|| format!("{mode} {} to {}", src.display(), out.display()))
+    }
+
+    async fn transfer(src: &Path, out: &Path, mode: Mode) -> Result<()> {
+        match mode {
+            Mode::Move => journal::move_across(src, out).await?,
+            Mode::Copy => {
+                fs::copy(src, out).await?;
+            }
+            Mode::Hardlink => fs::hard_link(src, out).await?,
+            #[cfg(unix)]
+            Mode::Symlink => fs::symlink(src, out).await?,
+            #[cfg(windows)]
+            Mode::Symlink => fs::symlink_file(src, out).await?,
+        }
+
         Ok(())
     }
 
@@ -159,18 +415,213 @@ impl Payload {
         }
     }
 
-    pub async fn write_all_to(&self, path: &Path) -> Result<()> {
-        self.write_fanart_to(path)
-            .await
-            .with_context(|| "write fanart")?;
-        self.write_poster_to(path)
-            .await
-            .with_context(|| "write poster")?;
-        self.write_subtitle_to(path)
-            .await
-            .with_context(|| "write subtitle")?;
-        self.write_nfo_to(path).await.with_context(|| "write nfo")?;
+    pub async fn write_all_to(&self, path: &Path, conflict: Conflict) -> Result<()> {
+        self.write_some_to(path, &Artifact::ALL, conflict).await
+    }
+
+    pub async fn write_some_to(
+        &self,
+        path: &Path,
+        artifacts: &[Artifact],
+        conflict: Conflict,
+    ) -> Result<()> {
+        if artifacts.contains(&Artifact::Fanart) {
+            self.write_fanart_to(path, conflict)
+                .await
+                .with_context(|| "write fanart")?;
+        }
+        if artifacts.contains(&Artifact::Poster) {
+            self.write_poster_to(path, conflict)
+                .await
+                .with_context(|| "write poster")?;
+        }
+        if artifacts.contains(&Artifact::Subtitle) {
+            self.write_subtitle_to(path, conflict)
+                .await
+                .with_context(|| "write subtitle")?;
+        }
+        if artifacts.contains(&Artifact::Nfo) {
+            self.write_nfo_to(path, conflict)
+                .await
+                .with_context(|| "write nfo")?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn print_plan_to(&self, path: &Path, mode: Mode, conflict: Conflict) -> Result<()> {
+        let nfo = self.nfo.to_string();
+        let mut artifacts = vec![
+            (self.fanart_filename(), self.nfo.fanart().len()),
+            (self.poster_filename(), self.nfo.poster().len()),
+            (self.nfo_filename(), nfo.len()),
+        ];
+        if !self.nfo.subtitle().is_empty() {
+            artifacts.push((self.subtitle_filename(), self.nfo.subtitle().len()));
+        }
+        for (artifact, size) in artifacts {
+            let target = path.join(artifact);
+            match Self::resolve(&target, size as u64, conflict).await? {
+                Some(file) => {
+                    info!("plan to write {}", file.display());
+                    self.bar.message(format!("write ... {}", file.display()));
+                }
+                None => {
+                    info!("plan to skip existing {} by {conflict}", target.display());
+                    self.bar
+                        .message(format!("{} ... {}", "skip".yellow(), target.display()));
+                }
+            }
+        }
+        for video in self.video.files() {
+            let src = video.location();
+            let size = fs::metadata(src)
+                .await
+                .with_context(|| format!("read metadata of {}", src.display()))?
+                .len();
+            let target = path.join(self.video_filename(video));
+            let Some(out) = Self::resolve(&target, size, conflict).await? else {
+                info!(
+                    "plan to skip existing video {} by {conflict}",
+                    target.display()
+                );
+                self.bar
+                    .message(format!("video already exists {}", target.display()));
+                continue;
+            };
+            info!("plan to {mode} {} to {}", src.display(), out.display());
+            self.bar.message(format!(
+                "{mode} ... {} {} {}",
+                src.display(),
+                "->".yellow(),
+                out.display()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn test_place() -> Result<()> {
+        let dir = temp_dir("payload-place");
+        let journal_path = dir.join("journal");
+        let journal = Journal::new(&journal_path);
+        let out = dir.join("a.mp4");
+        fs::write(&out, "old").await?;
+
+        // a failed transfer keeps the old file
+        let result = Payload::place(&journal, &dir.join("missing.mp4"), &out, Mode::Hardlink).await;
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&out).await?, "old");
+        assert!(!dir.join(".a.mp4.tmp").exists());
+
+        let src = dir.join("new.mp4");
+        fs::write(&src, "new").await?;
+        Payload::place(&journal, &src, &out, Mode::Move).await?;
+        assert_eq!(fs::read_to_string(&out).await?, "new");
+        assert!(!src.exists());
+
+        assert_eq!(Journal::undo(&journal_path).await?, 0);
+        assert_eq!(fs::read_to_string(&out).await?, "old");
+        assert_eq!(fs::read_to_string(&src).await?, "new");
+
+        fs::remove_dir_all(dir).await.ok();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resolve() -> Result<()> {
+        let dir = temp_dir("payload-resolve");
+        let file = dir.join("a.zh.srt");
+        let other = dir.join("b.srt");
+        let resolve = |file: PathBuf, size, conflict| async move {
+            Payload::resolve(&file, size, conflict).await.unwrap()
+        };
+
+        // nothing to conflict with
+        for conflict in [
+            Conflict::Skip,
+            Conflict::Overwrite,
+            Conflict::Rename,
+            Conflict::KeepLarger,
+        ] {
+            assert_eq!(
+                resolve(other.clone(), 1, conflict).await,
+                Some(other.clone())
+            );
+        }
+
+        fs::write(&file, "abc").await?;
+        assert_eq!(resolve(file.clone(), 1, Conflict::Skip).await, None);
+        assert_eq!(
+            resolve(file.clone(), 1, Conflict::Overwrite).await,
+            Some(file.clone())
+        );
+
+        // renamed as a whole by `rename_in` already
+        assert_eq!(resolve(file.clone(), 1, Conflict::Rename).await, None);
+
+        assert_eq!(
+            resolve(file.clone(), 4, Conflict::KeepLarger).await,
+            Some(file.clone())
+        );
+        assert_eq!(resolve(file.clone(), 3, Conflict::KeepLarger).await, None);
+        assert_eq!(resolve(file.clone(), 2, Conflict::KeepLarger).await, None);
+
+        fs::remove_dir_all(dir).await.ok();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_in() -> Result<()> {
+        let dir = temp_dir("payload-rename");
+        let (ty, _) = VideoType::parse("ABC-123");
+        let mut video = Video::new(ty);
+        for idx in [1, 2] {
+            let location = dir.join(format!("abc-123-cd{idx}.mp4"));
+            video.add_file(
+                VideoFile::builder()
+                    .location(&location)
+                    .ext("mp4")
+                    .idx(idx)
+                    .build(),
+            );
+        }
+        let mut payload = Payload::builder()
+            .video(video)
+            .nfo(Nfo::builder().id("ABC-123").build())
+            .bar(Arc::new(Bar::new().await))
+            .journal(Arc::new(Journal::new(dir.join("journal"))))
+            .build();
+
+        // only rename changes the name
+        fs::write(dir.join("ABC-123-CD2.mp4"), "").await?;
+        payload.rename_in(&dir, Conflict::Overwrite).await;
+        assert_eq!(payload.nfo_filename(), "ABC-123.nfo");
+
+        // any taken file moves the whole payload to the next free suffix
+        fs::write(dir.join("ABC-123 (1)-poster.jpg"), "").await?;
+        payload.rename_in(&dir, Conflict::Rename).await;
+        assert_eq!(
+            payload.filenames(&payload.name),
+            [
+                "ABC-123 (2)-fanart.jpg",
+                "ABC-123 (2)-poster.jpg",
+                "ABC-123 (2).nfo",
+                "ABC-123 (2).srt",
+                "ABC-123 (2)-CD1.mp4",
+                "ABC-123 (2)-CD2.mp4",
+            ]
+        );
 
+        fs::remove_dir_all(dir).await.ok();
         Ok(())
     }
 }
@@ -0,0 +1,71 @@
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use colored::Colorize;
+use log::info;
+use spider::{Candidate, Picker};
+use tokio::sync::Mutex;
+use video::VideoType;
+
+use super::bar::Bar;
+
+/// ask user in terminal to pick one of the candidates
+pub struct Prompt {
+    bar: Arc<Bar>,
+    lock: Mutex<()>,
+}
+
+impl Prompt {
+    pub fn new(bar: Arc<Bar>) -> Prompt {
+        Prompt {
+            bar,
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+#[async_trait]
+impl Picker for Prompt {
+    async fn pick(&self, key: &VideoType, candidates: &[Candidate]) -> Option<usize> {
+        let _guard = self.lock.lock().await;
+        self.bar.pause().await;
+
+        println!(
+            "{}",
+            format!("found {} candidates of {key}", candidates.len()).yellow()
+        );
+        for (idx, candidate) in candidates.iter().enumerate() {
+            println!(
+                "[{}] {} ({}) from {}",
+                idx + 1,
+                candidate.title,
+                if candidate.date.is_empty() {
+                    "?"
+                } else {
+                    candidate.date.as_str()
+                },
+                candidate.source
+            );
+        }
+        print!("choose one [1-{}], default 1: ", candidates.len());
+        io::stdout().flush().ok();
+
+        let line = tokio::task::spawn_blocking(|| {
+            let mut line = String::new();
+            io::stdin().read_line(&mut line).map(|_| line)
+        })
+        .await;
+        self.bar.resume().await;
+
+        let picked = line
+            .ok()
+            .and_then(|line| line.ok())
+            .and_then(|line| line.trim().parse::<usize>().ok())
+            .filter(|idx| (1..=candidates.len()).contains(idx))
+            .map(|idx| idx - 1);
+        info!("picked {picked:?} of {key}");
+
+        picked
+    }
+}
@@ -0,0 +1,288 @@
+use std::path::{MAIN_SEPARATOR, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use config::MediaServer;
+use http_client::Client;
+use log::{info, warn};
+use serde_json::{Value, json};
+
+/// ask media servers to scan the dirs with new videos, instead of waiting for
+/// their periodic scan
+pub struct Refresher {
+    servers: Vec<MediaServer>,
+    client: Client,
+}
+
+impl Refresher {
+    pub fn new(servers: Vec<MediaServer>, timeout: Duration) -> Result<Refresher> {
+        // media servers are usually in the local network, so no proxy
+        let client = Client::builder()
+            .timeout(timeout)
+            .interval(1)
+            .amount(10)
+            .build()
+            .with_context(|| "build http client")?;
+        let refresher = Refresher { servers, client };
+
+        Ok(refresher)
+    }
+
+    /// a failed server is logged and never fails the run
+    pub async fn refresh(&self, dirs: &[PathBuf]) {
+        if dirs.is_empty() {
+            return;
+        }
+
+        for server in self.servers.iter() {
+            match self.refresh_server(server, dirs).await {
+                Ok(_) => info!("ask {server} to refresh {} dir(s)", dirs.len()),
+                Err(e) => warn!("could not refresh {server}, caused by {e:?}"),
+            }
+        }
+    }
+
+    async fn refresh_server(&self, server: &MediaServer, dirs: &[PathBuf]) -> Result<()> {
+        match server {
+            MediaServer::Jellyfin { url, key } => {
+                let url = format!("{}/Library/Media/Updated", url.trim_end_matches('/'));
+                self.media_updated(&url, key, dirs).await
+            }
+            MediaServer::Emby { url, key } => {
+                let url = format!("{}/emby/Library/Media/Updated", url.trim_end_matches('/'));
+                self.media_updated(&url, key, dirs).await
+            }
+            MediaServer::Kodi {
+                url,
+                user,
+                password,
+            } => {
+                let url = format!("{}/jsonrpc", url.trim_end_matches('/'));
+                self.kodi_scan(&url, user.as_deref(), password.as_deref(), dirs)
+                    .await
+            }
+        }
+    }
+
+    /// jellyfin and emby share the same api
+    async fn media_updated(&self, url: &str, key: &str, dirs: &[PathBuf]) -> Result<()> {
+        let updates = dirs
+            .iter()
+            .map(|dir| json!({ "Path": dir, "UpdateType": "Created" }))
+            .collect::<Vec<_>>();
+        self.client
+            .wait()
+            .await
+            .post(url)
+            .header("X-Emby-Token", key)
+            .json(&json!({ "Updates": updates }))
+            .send()
+            .await
+            .with_context(|| format!("post {url}"))?
+            .error_for_status()
+            .with_context(|| "bad status")?;
+
+        Ok(())
+    }
+
+    /// kodi ignores a scan while another is running, so the whole library is
+    /// scanned if more than one dir changed
+    async fn kodi_scan(
+        &self,
+        url: &str,
+        user: Option<&str>,
+        password: Option<&str>,
+        dirs: &[PathBuf],
+    ) -> Result<()> {
+        let params = match dirs {
+            [dir] => {
+                let mut directory = dir.display().to_string();
+                if !directory.ends_with(MAIN_SEPARATOR) {
+                    directory.push(MAIN_SEPARATOR);
+                }
+                json!({ "directory": directory })
+            }
+            _ => json!({}),
+        };
+        let mut request = self.client.wait().await.post(url).json(&json!({
+            "jsonrpc": "2.0",
+            "method": "VideoLibrary.Scan",
+            "params": params,
+            "id": 1,
+        }));
+        if let Some(user) = user {
+            request = request.basic_auth(user, password);
+        }
+        let res = request
+            .send()
+            .await
+            .with_context(|| format!("post {url}"))?
+            .error_for_status()
+            .with_context(|| "bad status")?
+            .json::<Value>()
+            .await
+            .with_context(|| "decode response")?;
+        if let Some(error) = res.get("error") {
+            bail!("kodi returns error {error}");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Requests, stand_in};
+
+    use axum::http::StatusCode;
+    use pretty_assertions::assert_eq;
+
+    /// path, emby token, authorization and json body of each request
+    async fn seen(requests: &Requests) -> Vec<(String, Option<String>, Option<String>, Value)> {
+        requests
+            .lock()
+            .await
+            .iter()
+            .map(|request| {
+                let header = |name| request.header(name).map(|value| value.to_string());
+                (
+                    request.path.clone(),
+                    header("x-emby-token"),
+                    header("authorization"),
+                    request.json(),
+                )
+            })
+            .collect()
+    }
+
+    fn refresher(server: MediaServer) -> Result<Refresher> {
+        Refresher::new(vec![server], Duration::from_secs(5))
+    }
+
+    fn dirs() -> Vec<PathBuf> {
+        vec![
+            PathBuf::from("/lib/XXX/XXX-123"),
+            PathBuf::from("/lib/YYY/YYY-456"),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_jellyfin_and_emby() -> Result<()> {
+        let (url, requests) = stand_in(StatusCode::NO_CONTENT, Value::Null).await?;
+        let servers = [
+            MediaServer::Jellyfin {
+                url: format!("{url}/"),
+                key: "jellyfin-key".to_string(),
+            },
+            MediaServer::Emby {
+                url: url.clone(),
+                key: "emby-key".to_string(),
+            },
+        ];
+        for server in servers {
+            let refresher = refresher(server.clone())?;
+            refresher.refresh_server(&server, &dirs()).await?;
+        }
+
+        let body = json!({
+            "Updates": [
+                { "Path": "/lib/XXX/XXX-123", "UpdateType": "Created" },
+                { "Path": "/lib/YYY/YYY-456", "UpdateType": "Created" },
+            ]
+        });
+        let expected = vec![
+            (
+                "/Library/Media/Updated".to_string(),
+                Some("jellyfin-key".to_string()),
+                None,
+                body.clone(),
+            ),
+            (
+                "/emby/Library/Media/Updated".to_string(),
+                Some("emby-key".to_string()),
+                None,
+                body,
+            ),
+        ];
+        assert_eq!(seen(&requests).await, expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_kodi() -> Result<()> {
+        let (url, requests) = stand_in(
+            StatusCode::OK,
+            json!({ "id": 1, "jsonrpc": "2.0", "result": "OK" }),
+        )
+        .await?;
+        let server = MediaServer::Kodi {
+            url,
+            user: Some("kodi".to_string()),
+            password: Some("secret".to_string()),
+        };
+        let refresher = refresher(server.clone())?;
+        refresher.refresh_server(&server, &dirs()[..1]).await?;
+        refresher.refresh_server(&server, &dirs()).await?;
+
+        let request = |params| {
+            (
+                "/jsonrpc".to_string(),
+                None,
+                // base64 of kodi:secret
+                Some("Basic a29kaTpzZWNyZXQ=".to_string()),
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "VideoLibrary.Scan",
+                    "params": params,
+                    "id": 1,
+                }),
+            )
+        };
+        let expected = vec![
+            request(json!({ "directory": "/lib/XXX/XXX-123/" })),
+            request(json!({})),
+        ];
+        assert_eq!(seen(&requests).await, expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed() -> Result<()> {
+        let cases = [
+            (StatusCode::UNAUTHORIZED, Value::Null),
+            (
+                StatusCode::OK,
+                json!({ "id": 1, "jsonrpc": "2.0", "error": { "code": -32601 } }),
+            ),
+        ];
+        for (status, answer) in cases {
+            let (url, _) = stand_in(status, answer).await?;
+            let servers = [
+                MediaServer::Jellyfin {
+                    url: url.clone(),
+                    key: String::new(),
+                },
+                MediaServer::Kodi {
+                    url,
+                    user: None,
+                    password: None,
+                },
+            ];
+            for server in servers {
+                let refresher = refresher(server.clone())?;
+                let res = refresher.refresh_server(&server, &dirs()).await;
+                // jellyfin does not look into the body
+                if status == StatusCode::OK && matches!(server, MediaServer::Jellyfin { .. }) {
+                    assert!(res.is_ok(), "{server}: {res:?}");
+                } else {
+                    assert!(res.is_err(), "{server} should fail with {status}");
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
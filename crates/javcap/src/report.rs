@@ -0,0 +1,249 @@
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tokio::fs;
+use video::Video;
+
+use super::ledger::Outcome;
+
+/// what happened to a video while it is processed
+#[derive(Debug, Clone, Serialize)]
+pub struct Trace {
+    pub finders_succeed: Vec<String>,
+    pub finders_failed: Vec<FinderError>,
+    pub translated: bool,
+    pub subtitle: bool,
+    pub find_ms: u128,
+    pub translate_ms: u128,
+    #[serde(skip)]
+    queued: Instant,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FinderError {
+    pub finder: String,
+    pub error: String,
+}
+
+impl Trace {
+    pub fn new() -> Trace {
+        Trace {
+            finders_succeed: Vec::new(),
+            finders_failed: Vec::new(),
+            translated: false,
+            subtitle: false,
+            find_ms: 0,
+            translate_ms: 0,
+            queued: Instant::now(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Record {
+    pub id: String,
+    pub sources: Vec<PathBuf>,
+    pub outcome: Outcome,
+    pub output: Option<PathBuf>,
+    pub error: Option<String>,
+    #[serde(flatten)]
+    pub trace: Trace,
+    pub total_ms: u128,
+}
+
+impl Record {
+    pub fn new(
+        video: &Video,
+        trace: Trace,
+        outcome: Outcome,
+        output: Option<PathBuf>,
+        error: Option<String>,
+    ) -> Record {
+        Record {
+            id: video.ty().to_string(),
+            sources: video
+                .files()
+                .iter()
+                .map(|file| file.location().clone())
+                .collect(),
+            outcome,
+            output,
+            error,
+            total_ms: trace.queued.elapsed().as_millis(),
+            trace,
+        }
+    }
+}
+
+/// one record per video, written as csv if the path ends with `.csv`, else json
+pub struct Report {
+    path: PathBuf,
+    records: Vec<Record>,
+}
+
+impl Report {
+    pub fn new(path: impl Into<PathBuf>) -> Report {
+        Report {
+            path: path.into(),
+            records: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, record: Record) {
+        self.records.push(record);
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub async fn save(&self) -> Result<()> {
+        let is_csv = self
+            .path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let content = if is_csv {
+            self.to_csv()
+        } else {
+            serde_json::to_string_pretty(&self.records).with_context(|| "encode report")?
+        };
+        fs::write(&self.path, content)
+            .await
+            .with_context(|| format!("write report {}", self.path.display()))?;
+
+        Ok(())
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "id,sources,outcome,output,error,finders_succeed,finders_failed,translated,subtitle,find_ms,translate_ms,total_ms\n",
+        );
+        for record in self.records.iter() {
+            let sources = record
+                .sources
+                .iter()
+                .map(|source| source.display().to_string())
+                .collect::<Vec<_>>()
+                .join(";");
+            let output = record
+                .output
+                .as_ref()
+                .map(|output| output.display().to_string())
+                .unwrap_or_default();
+            let finders_failed = record
+                .trace
+                .finders_failed
+                .iter()
+                .map(|failed| format!("{}: {}", failed.finder, failed.error))
+                .collect::<Vec<_>>()
+                .join(";");
+            let fields = [
+                record.id.clone(),
+                sources,
+                record.outcome.to_string(),
+                output,
+                record.error.clone().unwrap_or_default(),
+                record.trace.finders_succeed.join(";"),
+                finders_failed,
+                record.trace.translated.to_string(),
+                record.trace.subtitle.to_string(),
+                record.trace.find_ms.to_string(),
+                record.trace.translate_ms.to_string(),
+                record.total_ms.to_string(),
+            ];
+            let line = fields
+                .iter()
+                .map(|field| Self::escape(field))
+                .collect::<Vec<_>>()
+                .join(",");
+            csv.push_str(&line);
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    fn escape(field: &str) -> String {
+        if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use video::{VideoFile, VideoType};
+
+    fn video(name: &str, files: &[&str]) -> Video {
+        let (ty, _) = VideoType::parse(name);
+        let mut video = Video::new(ty);
+        for (idx, file) in files.iter().enumerate() {
+            video.add_file(
+                VideoFile::builder()
+                    .location(Path::new(file))
+                    .ext("mp4")
+                    .idx(idx as u32)
+                    .build(),
+            );
+        }
+        video
+    }
+
+    #[test]
+    fn test_escape() {
+        let cases = [
+            ("plain", "plain"),
+            ("a,b", "\"a,b\""),
+            ("say \"hi\"", "\"say \"\"hi\"\"\""),
+            ("line\nbreak", "\"line\nbreak\""),
+        ];
+        for (field, expected) in cases {
+            assert_eq!(Report::escape(field), expected, "{field}");
+        }
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let mut report = Report::new("report.csv");
+        let mut trace = Trace::new();
+        trace.finders_succeed = vec!["javdb".to_string(), "airav".to_string()];
+        report.add(Record::new(
+            &video("ABC-001", &["/in/ABC-001-1.mp4", "/in/ABC-001-2.mp4"]),
+            trace,
+            Outcome::Succeed,
+            Some(PathBuf::from("/out/ABC/ABC-001")),
+            None,
+        ));
+        let mut trace = Trace::new();
+        trace.finders_failed = vec![FinderError {
+            finder: "javdb".to_string(),
+            error: "not found, \"ABC-002\"".to_string(),
+        }];
+        report.add(Record::new(
+            &video("ABC-002", &["/in/ABC-002.mp4"]),
+            trace,
+            Outcome::Interrupted,
+            None,
+            Some("line 1\nline 2".to_string()),
+        ));
+
+        // total time varies from run to run
+        for record in report.records.iter_mut() {
+            record.total_ms = 0;
+        }
+        assert_eq!(
+            report.to_csv(),
+            concat!(
+                "id,sources,outcome,output,error,finders_succeed,finders_failed,translated,subtitle,find_ms,translate_ms,total_ms\n",
+                "ABC-001,/in/ABC-001-1.mp4;/in/ABC-001-2.mp4,succeed,/out/ABC/ABC-001,,javdb;airav,,false,false,0,0,0\n",
+                "ABC-002,/in/ABC-002.mp4,interrupted,,\"line 1\nline 2\",,\"javdb: not found, \"\"ABC-002\"\"\",false,false,0,0,0\n",
+            )
+        );
+    }
+}
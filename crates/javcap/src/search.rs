@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use config::Config;
+use spider::Found;
+use video::VideoType;
+
+use super::helper::Helper;
+use super::journal::JournalKind;
+
+/// search given name in finders, without touching any file
+pub async fn search(
+    config: &Config,
+    name: &str,
+    finders: &[String],
+    translate: bool,
+) -> Result<(VideoType, Found)> {
+    let (ty, _) = VideoType::parse(name);
+    let mut helper = Helper::new(config, None, JournalKind::Run).with_context(|| "build helper")?;
+    helper.spider = helper
+        .spider
+        .only(finders)
+        .with_context(|| "filter finders")?;
+    let helper = Arc::new(helper);
+
+    let mut found = helper
+        .spider
+        .search(ty.clone())
+        .await
+        .with_context(|| format!("search {ty}"))?;
+    if let Some(nfo) = found.nfo.as_mut() {
+        nfo.auto_fix_by_key(&ty);
+        nfo.traditional_to_simplified();
+        if translate {
+            Helper::translate(&helper, nfo)
+                .await
+                .with_context(|| "translate nfo")?;
+        }
+    }
+
+    Ok((ty, found))
+}
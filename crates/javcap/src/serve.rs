@@ -0,0 +1,327 @@
+use std::fmt::{self, Display};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::Local;
+use config::Config;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::fs;
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use tokio::sync::watch::Receiver;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use super::app::App;
+use super::bar::Bar;
+use super::helper::cache_dir;
+use super::search::search;
+use super::shutdown;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+enum JobKind {
+    #[serde(rename = "run")]
+    Run,
+
+    #[serde(rename = "scrape")]
+    Scrape,
+}
+
+impl Display for JobKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobKind::Run => write!(f, "run"),
+            JobKind::Scrape => write!(f, "scrape"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+enum JobState {
+    #[serde(rename = "running")]
+    Running,
+
+    #[serde(rename = "finished")]
+    Finished,
+
+    #[serde(rename = "failed")]
+    Failed,
+}
+
+/// the latest job started by the api
+#[derive(Debug, Clone, Serialize)]
+struct Job {
+    id: u64,
+    kind: JobKind,
+    path: Option<PathBuf>,
+    state: JobState,
+    error: Option<String>,
+    started_at: String,
+    finished_at: Option<String>,
+}
+
+struct Current {
+    job: Job,
+    bar: Arc<Bar>,
+    handle: JoinHandle<()>,
+}
+
+struct Server {
+    config: Config,
+    stop: Receiver<Option<Instant>>,
+    current: Mutex<Option<Current>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RunRequest {
+    dry_run: bool,
+    force: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ScrapeRequest {
+    path: Option<PathBuf>,
+    id: Option<String>,
+    dry_run: bool,
+    force: bool,
+    translate: bool,
+}
+
+/// serve the api on addr until interrupted, only one job runs at a time
+///
+/// - `POST /run` process all videos in input path
+/// - `POST /scrape` process one path, or search one id without touching any file
+/// - `GET /progress` the latest job and its progress
+/// - `GET /report` report of the latest finished job
+pub async fn serve(config: Config, addr: SocketAddr) -> Result<()> {
+    let stop = shutdown::listen(config.grace());
+    let server = Arc::new(Server {
+        config,
+        stop: stop.clone(),
+        current: Mutex::new(None),
+    });
+    let router = Router::new()
+        .route("/run", post(run))
+        .route("/scrape", post(scrape))
+        .route("/progress", get(progress))
+        .route("/report", get(report))
+        .with_state(server.clone());
+
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("bind {addr}"))?;
+    if !addr.ip().is_loopback() {
+        warn!("listen on {addr}, the api has no auth, anyone on the network can use it");
+    }
+    info!("listen on http://{addr}");
+    println!("listen on http://{addr}");
+    axum::serve(listener, router)
+        .with_graceful_shutdown(async move {
+            shutdown::stopped(stop).await;
+        })
+        .await
+        .with_context(|| "serve api")?;
+
+    // the job sees the same stop, and finishes in grace time
+    let current = server.current.lock().await.take();
+    if let Some(current) = current {
+        current.handle.await.ok();
+    }
+    info!("server stopped");
+
+    Ok(())
+}
+
+fn report_path() -> PathBuf {
+    cache_dir().join("report.json")
+}
+
+fn now() -> String {
+    Local::now().to_rfc3339()
+}
+
+fn error_response(status: StatusCode, msg: impl Into<String>) -> Response {
+    (status, Json(json!({ "error": msg.into() }))).into_response()
+}
+
+async fn run(State(server): State<Arc<Server>>, req: Option<Json<RunRequest>>) -> Response {
+    let req = req.map(|Json(req)| req).unwrap_or_default();
+    server
+        .start(JobKind::Run, None, req.dry_run, req.force)
+        .await
+}
+
+async fn scrape(State(server): State<Arc<Server>>, Json(req): Json<ScrapeRequest>) -> Response {
+    match (req.path, req.id) {
+        (Some(path), None) => {
+            if !path.exists() {
+                return error_response(
+                    StatusCode::NOT_FOUND,
+                    format!("{} not found", path.display()),
+                );
+            }
+            server
+                .start(JobKind::Scrape, Some(path), req.dry_run, req.force)
+                .await
+        }
+        (None, Some(id)) => match search(&server.config, &id, &[], req.translate).await {
+            Ok((_, found)) => match found.nfo {
+                Some(nfo) => Json(nfo).into_response(),
+                None => {
+                    let failed = found
+                        .failed
+                        .into_iter()
+                        .map(|(finder, error)| json!({ "finder": finder, "error": error }))
+                        .collect::<Vec<_>>();
+                    (
+                        StatusCode::NOT_FOUND,
+                        Json(json!({
+                            "error": format!("could not find anything about {id} in all finders"),
+                            "finders_failed": failed,
+                        })),
+                    )
+                        .into_response()
+                }
+            },
+            Err(e) => {
+                error!("search {id} failed, caused by {e:?}");
+                error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}"))
+            }
+        },
+        _ => error_response(StatusCode::BAD_REQUEST, "give either path or id"),
+    }
+}
+
+async fn progress(State(server): State<Arc<Server>>) -> Response {
+    let current = server.current.lock().await;
+    let Some(ref current) = *current else {
+        return Json(json!({ "job": null })).into_response();
+    };
+
+    let (done, total) = current.bar.progress().await;
+    Json(json!({
+        "job": current.job,
+        "done": done,
+        "total": total,
+    }))
+    .into_response()
+}
+
+async fn report() -> Response {
+    let path = report_path();
+    if !path.exists() {
+        return error_response(StatusCode::NOT_FOUND, "no report yet");
+    }
+
+    match fs::read_to_string(&path).await {
+        Ok(content) => ([("content-type", "application/json")], content).into_response(),
+        Err(e) => {
+            error!("could not read {}, caused by {e}", path.display());
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        }
+    }
+}
+
+impl Server {
+    async fn start(
+        self: &Arc<Self>,
+        kind: JobKind,
+        path: Option<PathBuf>,
+        dry_run: bool,
+        force: bool,
+    ) -> Response {
+        if self.stop.borrow().is_some() {
+            return error_response(StatusCode::SERVICE_UNAVAILABLE, "server is stopping");
+        }
+
+        let mut current = self.current.lock().await;
+        let id = match *current {
+            Some(ref current) if current.job.state == JobState::Running => {
+                return error_response(
+                    StatusCode::CONFLICT,
+                    format!("job {} is still running", current.job.id),
+                );
+            }
+            Some(ref current) => current.job.id + 1,
+            None => 1,
+        };
+
+        let app = App::builder()
+            .config(self.config.clone())
+            .dry_run(dry_run)
+            .force(force)
+            .report(report_path())
+            .stop(self.stop.clone())
+            .build()
+            .await;
+        let app = match app {
+            Ok(app) => app,
+            Err(e) => {
+                error!("could not init app, caused by {e:?}");
+                return error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}"));
+            }
+        };
+
+        let job = Job {
+            id,
+            kind,
+            path: path.clone(),
+            state: JobState::Running,
+            error: None,
+            started_at: now(),
+            finished_at: None,
+        };
+        info!("start job {id}, {kind}");
+        let bar = app.bar();
+        let server = self.clone();
+        let handle = tokio::spawn(async move {
+            let res = match path {
+                Some(path) => app.scrape(&path).await,
+                None => app.run().await,
+            };
+            server.finish(id, res).await;
+        });
+        *current = Some(Current {
+            job: job.clone(),
+            bar,
+            handle,
+        });
+
+        (StatusCode::ACCEPTED, Json(job)).into_response()
+    }
+
+    async fn finish(&self, id: u64, res: Result<()>) {
+        let mut current = self.current.lock().await;
+        let Some(ref mut current) = *current else {
+            return;
+        };
+        if current.job.id != id {
+            return;
+        }
+
+        let job = &mut current.job;
+        job.finished_at = Some(now());
+        match res {
+            Ok(_) => {
+                info!("job {id} finished");
+                job.state = JobState::Finished;
+            }
+            Err(e) => {
+                error!("job {id} failed, caused by {e:?}");
+                job.state = JobState::Failed;
+                job.error = Some(format!("{e:#}"));
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::future;
+use std::io;
+use std::process;
+use std::time::Duration;
+
+use log::{error, warn};
+use tokio::signal;
+use tokio::sync::watch::{self, Receiver};
+use tokio::time::Instant;
+
+/// listen to ctrl-c and sigterm, the first one sends the deadline to stop
+/// before, the second one exits at once
+pub fn listen(grace: Duration) -> Receiver<Option<Instant>> {
+    let (tx, rx) = watch::channel(None);
+    tokio::spawn(async move {
+        if let Err(e) = wait_for_signal().await {
+            error!("could not listen to signal, caused by {e}");
+            // keep the sender, so that nobody treats it as a stop
+            future::pending::<()>().await;
+        }
+        warn!("interrupted, stop in {} seconds", grace.as_secs());
+        tx.send(Some(Instant::now() + grace)).ok();
+
+        if wait_for_signal().await.is_ok() {
+            warn!("interrupted again, exit at once");
+            process::exit(130);
+        }
+    });
+
+    rx
+}
+
+/// wait until stop is asked, return the deadline
+pub async fn stopped(mut rx: Receiver<Option<Instant>>) -> Instant {
+    let deadline = rx
+        .wait_for(|deadline| deadline.is_some())
+        .await
+        .map(|deadline| deadline.unwrap_or_else(Instant::now));
+    match deadline {
+        Ok(deadline) => deadline,
+        Err(_) => future::pending().await,
+    }
+}
+
+/// wait until the deadline after stop is asked
+pub async fn expired(rx: Receiver<Option<Instant>>) {
+    let deadline = stopped(rx).await;
+    tokio::time::sleep_until(deadline).await;
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> io::Result<()> {
+    use signal::unix::{SignalKind, signal};
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        res = signal::ctrl_c() => res,
+        _ = terminate.recv() => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> io::Result<()> {
+    signal::ctrl_c().await
+}
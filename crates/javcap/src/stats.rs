@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use config::Config;
+use log::warn;
+use serde::Serialize;
+
+use super::library::{NfoInfo, nfo_files};
+
+/// aggregates of all nfo in the library
+#[derive(Debug, Default, Serialize)]
+pub struct Stats {
+    pub total: usize,
+    pub unreadable: usize,
+    pub runtime: u64,
+    pub with_subtitle: usize,
+    pub without_subtitle: usize,
+    pub without_plot: usize,
+    pub without_director: usize,
+    pub without_rating: usize,
+    pub studios: BTreeMap<String, usize>,
+    pub actors: BTreeMap<String, usize>,
+    pub genres: BTreeMap<String, usize>,
+    pub countries: BTreeMap<String, usize>,
+    pub years: BTreeMap<String, usize>,
+}
+
+impl Stats {
+    pub async fn collect(config: &Config) -> Result<Stats> {
+        let mut files = Vec::new();
+        for output in config.libraries() {
+            let found = nfo_files(output)
+                .await
+                .with_context(|| format!("find nfo in {}", output.display()))?;
+            files.extend(found);
+        }
+        // libraries may be nested
+        files.sort();
+        files.dedup();
+
+        let mut stats = Stats::default();
+        for file in files {
+            let info = match NfoInfo::read(&file).await {
+                Ok(info) => info,
+                Err(e) => {
+                    warn!("skip {}, caused by {e:?}", file.display());
+                    stats.unreadable += 1;
+                    continue;
+                }
+            };
+
+            let subtitle = file.with_extension("srt");
+            if subtitle.exists() {
+                stats.with_subtitle += 1;
+            } else {
+                stats.without_subtitle += 1;
+            }
+            stats.add(info);
+        }
+
+        Ok(stats)
+    }
+
+    fn add(&mut self, info: NfoInfo) {
+        self.total += 1;
+        self.runtime += info.runtime.unwrap_or_default() as u64;
+        if info.plot.is_empty() {
+            self.without_plot += 1;
+        }
+        if info.director.is_empty() {
+            self.without_director += 1;
+        }
+        if info.rating.is_none_or(|rating| rating <= 0.0) {
+            self.without_rating += 1;
+        }
+
+        Self::count(&mut self.studios, info.studio);
+        Self::count(&mut self.countries, info.country);
+        let year = info.premiered.chars().take(4).collect::<String>();
+        Self::count(&mut self.years, year);
+        for actor in info.actors {
+            Self::count(&mut self.actors, actor);
+        }
+        for genre in info.genres {
+            Self::count(&mut self.genres, genre);
+        }
+    }
+
+    fn count(counts: &mut BTreeMap<String, usize>, key: String) {
+        let key = if key.is_empty() {
+            "未知".to_string()
+        } else {
+            key
+        };
+        *counts.entry(key).or_default() += 1;
+    }
+
+    /// print as table, only the top ones of each group are shown
+    pub fn print_table(&self, top: usize) {
+        println!("{}", "Library".yellow());
+        for (name, value) in [
+            ("total", self.total.to_string()),
+            ("unreadable nfo", self.unreadable.to_string()),
+            (
+                "runtime",
+                format!("{}h{}m", self.runtime / 60, self.runtime % 60),
+            ),
+            ("with subtitle", self.with_subtitle.to_string()),
+            ("without subtitle", self.without_subtitle.to_string()),
+            ("without plot", self.without_plot.to_string()),
+            ("without director", self.without_director.to_string()),
+            ("without rating", self.without_rating.to_string()),
+        ] {
+            println!("  {name:<20}{value:>10}");
+        }
+
+        for (title, counts) in [
+            ("Studios", &self.studios),
+            ("Actors", &self.actors),
+            ("Genres", &self.genres),
+            ("Countries", &self.countries),
+        ] {
+            Self::print_counts(title, counts, top, true);
+        }
+        Self::print_counts("Years", &self.years, usize::MAX, false);
+    }
+
+    fn print_counts(title: &str, counts: &BTreeMap<String, usize>, top: usize, by_count: bool) {
+        println!();
+        println!("{}", format!("{title} ({})", counts.len()).yellow());
+        let mut counts = counts.iter().collect::<Vec<_>>();
+        if by_count {
+            counts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        }
+        for (name, count) in counts.iter().take(top) {
+            let width = name.chars().count() + (name.len() - name.chars().count()) / 2;
+            let padding = 20usize.saturating_sub(width);
+            println!("  {name}{}{count:>10}", " ".repeat(padding));
+        }
+        if counts.len() > top {
+            println!("  ... {} more", counts.len() - top);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::Router;
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use serde_json::Value;
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+
+/// a request seen by a stand-in server
+#[derive(Debug)]
+pub struct Request {
+    pub method: Method,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    /// body decoded as json, null if it is not
+    pub fn json(&self) -> Value {
+        serde_json::from_str(&self.body).unwrap_or_default()
+    }
+}
+
+pub type Requests = Arc<Mutex<Vec<Request>>>;
+
+/// a stand-in http server on a random port, which records every request and
+/// answers all of them with status and answer, return its url
+pub async fn stand_in(status: StatusCode, answer: Value) -> Result<(String, Requests)> {
+    let requests = Requests::default();
+    let answer = if answer.is_null() {
+        String::new()
+    } else {
+        answer.to_string()
+    };
+    let router = Router::new()
+        .fallback(
+            move |State(requests): State<Requests>,
+                  method: Method,
+                  uri: Uri,
+                  Query(query): Query<Vec<(String, String)>>,
+                  headers: HeaderMap,
+                  body: String| async move {
+                let request = Request {
+                    method,
+                    path: uri.path().to_string(),
+                    query,
+                    headers,
+                    body,
+                };
+                requests.lock().await.push(request);
+                (status, answer)
+            },
+        )
+        .with_state(requests.clone());
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}", listener.local_addr()?);
+    tokio::spawn(async move { axum::serve(listener, router).await });
+
+    Ok((url, requests))
+}
+
+/// an empty dir of the test in temp dir
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("javcap-{name}-{}", std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,311 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use config::{Config, Method, Notify, NotifyOn};
+use http_client::Client;
+use log::{info, warn};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// something worth telling the webhooks
+#[derive(Debug, Serialize)]
+#[serde(tag = "event")]
+pub enum Notice {
+    #[serde(rename = "run")]
+    Run {
+        ok: Vec<String>,
+        failed: Vec<String>,
+        kept: Vec<String>,
+        interrupted: Vec<String>,
+    },
+
+    #[serde(rename = "succeed")]
+    Succeed { id: String, output: PathBuf },
+
+    #[serde(rename = "failed")]
+    Failed { id: String, error: String },
+}
+
+impl Notice {
+    fn on(&self) -> NotifyOn {
+        match self {
+            Notice::Run { .. } => NotifyOn::Run,
+            Notice::Succeed { .. } => NotifyOn::Succeed,
+            Notice::Failed { .. } => NotifyOn::Failed,
+        }
+    }
+
+    /// fields of the notice, with the count of every list
+    fn fields(&self) -> Result<Map<String, Value>> {
+        let Value::Object(mut fields) =
+            serde_json::to_value(self).with_context(|| "encode notice")?
+        else {
+            return Ok(Map::new());
+        };
+        let counts = fields
+            .iter()
+            .filter_map(|(key, value)| {
+                value
+                    .as_array()
+                    .map(|list| (format!("{key}_count"), Value::from(list.len())))
+            })
+            .collect::<Vec<_>>();
+        fields.extend(counts);
+
+        Ok(fields)
+    }
+}
+
+/// call the webhooks in config, a failed call is logged and never fails the run
+pub struct Notifier {
+    hooks: Vec<Notify>,
+    client: Client,
+}
+
+impl Notifier {
+    pub fn new(config: &Config) -> Result<Notifier> {
+        // hooks are usually in the local network, so no proxy
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.network.timeout))
+            .interval(1)
+            .amount(10)
+            .build()
+            .with_context(|| "build http client")?;
+        let notifier = Notifier {
+            hooks: config.notify.clone(),
+            client,
+        };
+
+        Ok(notifier)
+    }
+
+    pub async fn send(&self, notice: Notice) {
+        let on = notice.on();
+        let hooks = self
+            .hooks
+            .iter()
+            .filter(|hook| hook.on.contains(&on))
+            .collect::<Vec<_>>();
+        if hooks.is_empty() {
+            return;
+        }
+
+        let fields = match notice.fields() {
+            Ok(fields) => fields,
+            Err(e) => {
+                warn!("could not notify {on}, caused by {e:?}");
+                return;
+            }
+        };
+        for hook in hooks {
+            match self.call(hook, &fields).await {
+                Ok(_) => info!("notify {on} to {}", hook.url),
+                Err(e) => warn!("could not notify {on} to {}, caused by {e:?}", hook.url),
+            }
+        }
+    }
+
+    async fn call(&self, hook: &Notify, fields: &Map<String, Value>) -> Result<()> {
+        let client = self.client.wait().await;
+        let request = match hook.method {
+            // a GET request has no body, the fields go to the query string instead
+            Method::Get => {
+                let query = fields
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), text_of(value)))
+                    .collect::<Vec<_>>();
+                client.get(&hook.url).query(&query)
+            }
+            Method::Post | Method::Put => {
+                let body = match hook.body {
+                    Some(ref template) => render(template, fields),
+                    None => serde_json::to_string(fields).with_context(|| "encode body")?,
+                };
+                let request = match hook.method {
+                    Method::Put => client.put(&hook.url),
+                    _ => client.post(&hook.url),
+                };
+                request
+                    .header("content-type", "application/json")
+                    .body(body)
+            }
+        };
+        request
+            .send()
+            .await
+            .with_context(|| format!("{} {}", hook.method, hook.url))?
+            .error_for_status()
+            .with_context(|| "bad status")?;
+
+        Ok(())
+    }
+}
+
+/// plain text of the value, lists are joined by `, `
+fn text_of(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(list) => list
+            .iter()
+            .map(|item| match item {
+                Value::String(text) => text.clone(),
+                item => item.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        value => value.to_string(),
+    }
+}
+
+/// replace `{{key}}` with the text of the value escaped for a json string
+fn render(template: &str, fields: &Map<String, Value>) -> String {
+    let mut body = template.to_string();
+    for (key, value) in fields {
+        let escaped = serde_json::to_string(&text_of(value)).unwrap_or_default();
+        let escaped = escaped
+            .strip_prefix('"')
+            .and_then(|escaped| escaped.strip_suffix('"'))
+            .unwrap_or_default();
+        body = body.replace(&format!("{{{{{key}}}}}"), escaped);
+    }
+
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::stand_in;
+
+    use axum::http::StatusCode;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn notifier(hooks: Vec<Notify>) -> Result<Notifier> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(5))
+            .interval(1)
+            .amount(10)
+            .build()
+            .with_context(|| "build http client")?;
+
+        Ok(Notifier { hooks, client })
+    }
+
+    fn hook(url: String, method: Method, body: Option<&str>, on: NotifyOn) -> Notify {
+        Notify {
+            url,
+            method,
+            body: body.map(|body| body.to_string()),
+            on: vec![on],
+        }
+    }
+
+    fn run() -> Notice {
+        Notice::Run {
+            ok: vec!["ABC-001".to_string(), "ABC-002".to_string()],
+            failed: vec!["ABC-003".to_string()],
+            kept: Vec::new(),
+            interrupted: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_fields() -> Result<()> {
+        let fields = run().fields()?;
+        assert_eq!(
+            Value::Object(fields),
+            json!({
+                "event": "run",
+                "ok": ["ABC-001", "ABC-002"],
+                "ok_count": 2,
+                "failed": ["ABC-003"],
+                "failed_count": 1,
+                "kept": [],
+                "kept_count": 0,
+                "interrupted": [],
+                "interrupted_count": 0,
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render() -> Result<()> {
+        let template = r#"{"text": "{{event}}: ok {{ok_count}} ({{ok}}), {{missing}}"}"#;
+        assert_eq!(
+            render(template, &run().fields()?),
+            r#"{"text": "run: ok 2 (ABC-001, ABC-002), {{missing}}"}"#
+        );
+
+        // the body stays valid json whatever the error says
+        let notice = Notice::Failed {
+            id: "ABC-003".to_string(),
+            error: "not found in \"javdb\"\ncaused by: 404".to_string(),
+        };
+        let body = render(r#"{"text": "{{id}} {{error}}"}"#, &notice.fields()?);
+        assert_eq!(
+            serde_json::from_str::<Value>(&body)?,
+            json!({ "text": "ABC-003 not found in \"javdb\"\ncaused by: 404" })
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send() -> Result<()> {
+        let (url, requests) = stand_in(StatusCode::OK, Value::Null).await?;
+        let notifier = notifier(vec![
+            hook(
+                format!("{url}/post"),
+                Method::Post,
+                Some(r#"{"text": "ok {{ok_count}}"}"#),
+                NotifyOn::Run,
+            ),
+            hook(format!("{url}/put"), Method::Put, None, NotifyOn::Run),
+            hook(
+                format!("{url}/get"),
+                Method::Get,
+                Some("ignored"),
+                NotifyOn::Run,
+            ),
+            hook(
+                format!("{url}/failed"),
+                Method::Post,
+                None,
+                NotifyOn::Failed,
+            ),
+        ])?;
+        notifier.send(run()).await;
+
+        let requests = requests.lock().await;
+        let paths = requests
+            .iter()
+            .map(|request| request.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["/post", "/put", "/get"]);
+
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].body, r#"{"text": "ok 2"}"#);
+        assert_eq!(requests[0].header("content-type"), Some("application/json"));
+
+        assert_eq!(requests[1].method, "PUT");
+        assert_eq!(requests[1].json(), Value::Object(run().fields()?));
+
+        assert_eq!(requests[2].method, "GET");
+        assert_eq!(requests[2].body, "");
+        assert_eq!(requests[2].header("content-type"), None);
+        let query = requests[2]
+            .query
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect::<Vec<_>>();
+        assert!(query.contains(&("event", "run")));
+        assert!(query.contains(&("ok", "ABC-001, ABC-002")));
+        assert!(query.contains(&("failed_count", "1")));
+
+        Ok(())
+    }
+}
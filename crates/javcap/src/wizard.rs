@@ -0,0 +1,103 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+use config::{Config, Translator};
+
+/// ask for the values step by step and write a config to `path`
+pub async fn init(path: PathBuf, force: bool) -> Result<()> {
+    if path.exists() && !force {
+        bail!(
+            "config already exists in {}, use --force to overwrite it",
+            path.display()
+        );
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let content = ask().with_context(|| "ask for config")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("create dir {}", parent.display()))?;
+        }
+        fs::write(&path, content).with_context(|| format!("write {}", path.display()))?;
+        println!("{} {}", "config written to".green(), path.display());
+
+        Ok(())
+    })
+    .await?
+}
+
+fn ask() -> Result<String> {
+    let input = ask_path("input path")?;
+    let output = ask_path("output path")?;
+
+    let mut translators = Vec::new();
+    loop {
+        let ty = ask_line(
+            "add translator [youdao/deepseek/openai/deepl], empty to finish",
+            "",
+        )?;
+        let translator = match ty.as_str() {
+            "" => break,
+            "youdao" => Translator::Youdao {
+                key: ask_line("youdao key", "")?,
+                secret: ask_line("youdao secret", "")?,
+            },
+            "deepseek" => Translator::DeepSeek {
+                base: ask_line("deepseek base", "https://api.deepseek.com")?,
+                model: ask_line("deepseek model", "deepseek-chat")?,
+                key: ask_line("deepseek key", "")?,
+            },
+            "openai" => Translator::Openai {
+                base: ask_line("openai base", "https://models.inference.ai.azure.com")?,
+                model: ask_line("openai model", "gpt-4o")?,
+                key: ask_line("openai key", "")?,
+            },
+            "deepl" => Translator::DeepL,
+            _ => {
+                println!("{}", format!("unknown translator {ty}").yellow());
+                continue;
+            }
+        };
+        translators.push(translator);
+    }
+
+    let proxy = ask_line("proxy, like http://127.0.0.1:7890, empty for none", "")?;
+    let proxy = (!proxy.is_empty()).then_some(proxy);
+
+    Config::generate(&input, &output, &translators, proxy.as_deref())
+}
+
+fn ask_path(name: &str) -> Result<PathBuf> {
+    loop {
+        let path = PathBuf::from(ask_line(name, "")?);
+        if path.is_absolute() {
+            return Ok(path);
+        }
+
+        println!("{}", format!("{name} should be an absolute path").yellow());
+    }
+}
+
+fn ask_line(question: &str, default: &str) -> Result<String> {
+    if default.is_empty() {
+        print!("{question}: ");
+    } else {
+        print!("{question}, default {default}: ");
+    }
+    io::stdout().flush().ok();
+
+    let mut line = String::new();
+    if io::stdin().lock().read_line(&mut line)? == 0 {
+        bail!("input closed");
+    }
+    let line = line.trim();
+
+    Ok(if line.is_empty() {
+        default.to_string()
+    } else {
+        line.to_string()
+    })
+}
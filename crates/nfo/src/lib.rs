@@ -7,10 +7,11 @@ use educe::Educe;
 use getset::{Getters, MutGetters, Setters};
 use indoc::writedoc;
 use quick_xml::escape::escape;
+use serde::{Serialize, Serializer};
 use validator::Validate;
 use video::VideoType;
 
-#[derive(Setters, Getters, MutGetters, Validate, Educe)]
+#[derive(Setters, Getters, MutGetters, Validate, Educe, Serialize)]
 #[educe(PartialEq)]
 pub struct Nfo {
     id: String,
@@ -57,15 +58,18 @@ pub struct Nfo {
     #[getset(set = "pub", get = "pub")]
     #[validate(length(min = 1, message = "empty"))]
     #[educe(PartialEq(ignore))]
+    #[serde(skip)]
     poster: Vec<u8>,
 
     #[getset(set = "pub", get = "pub")]
     #[validate(length(min = 1, message = "empty"))]
     #[educe(PartialEq(ignore))]
+    #[serde(skip)]
     fanart: Vec<u8>,
 
     #[getset(set = "pub", get = "pub")]
     #[educe(PartialEq(ignore))]
+    #[serde(skip)]
     subtitle: Vec<u8>,
 }
 
@@ -338,6 +342,12 @@ impl Display for Country {
     }
 }
 
+impl Serialize for Country {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 impl Merge for Country {
     fn merge(&mut self, other: Self) {
         if Country::Unknown == *self {
@@ -371,6 +381,12 @@ impl Display for Mpaa {
     }
 }
 
+impl Serialize for Mpaa {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 impl Merge for Mpaa {
     fn merge(&mut self, other: Self) {
         if *self < other {
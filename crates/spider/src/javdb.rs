@@ -1,4 +1,5 @@
 use std::fmt::{self, Display};
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{Context, Result, anyhow, bail};
@@ -10,7 +11,7 @@ use nfo::{Country, Mpaa, Nfo};
 use scraper::Html;
 use video::VideoType;
 
-use super::{Finder, select, which_country};
+use super::{Candidate, Finder, Picker, pick, select, which_country};
 
 const HOST: &str = "https://javdb.com";
 
@@ -25,10 +26,18 @@ select!(
     detail_value: "span"
 );
 
+struct HomeItem {
+    title: Option<String>,
+    date: Option<String>,
+    rating: Option<f64>,
+    url: Option<String>,
+}
+
 pub struct Javdb {
     base_url: String,
     client: Client,
     selectors: Selectors,
+    picker: Option<Arc<dyn Picker>>,
 }
 
 #[bon]
@@ -38,6 +47,7 @@ impl Javdb {
         base_url: Option<String>,
         timeout: Duration,
         proxy: Option<String>,
+        picker: Option<Arc<dyn Picker>>,
     ) -> Result<Javdb> {
         let client = Client::builder()
             .timeout(timeout)
@@ -55,6 +65,7 @@ impl Javdb {
             base_url,
             client,
             selectors,
+            picker,
         };
         Ok(javdb)
     }
@@ -109,57 +120,88 @@ impl Javdb {
             .await?
             .text()
             .await?;
-        let html = Html::parse_document(&text);
 
-        let name = key.to_string();
-        let Some(item) = html.select(&self.selectors.home_item).find(|node| {
-            node.select(&self.selectors.home_item_id)
-                .next()
-                .map(|node| node.text().collect::<String>() == name)
-                .unwrap_or(false)
-        }) else {
+        let items = self.parse_home_items(key, &text);
+        let candidates = items
+            .into_iter()
+            .map(|item| {
+                let candidate = Candidate {
+                    title: item.title.clone().unwrap_or_default(),
+                    date: item.date.clone().unwrap_or_default(),
+                    source: self.to_string(),
+                };
+                (candidate, item)
+            })
+            .collect();
+        let Some(item) = pick(self.picker.as_ref(), key, candidates).await else {
             bail!("item not found");
         };
 
-        if let Some(date) = item
-            .select(&self.selectors.home_date)
-            .next()
-            .map(|node| node.text().collect::<String>())
-        {
-            nfo.set_premiered(date.trim().to_string());
+        if let Some(date) = item.date {
+            nfo.set_premiered(date);
         }
-
-        if let Some(rating) = item
-            .select(&self.selectors.home_rating)
-            .next()
-            .and_then(|node| node.text().last().map(|text| text.trim()))
-            .map(|text| {
-                text.chars()
-                    .take_while(|c| c.is_ascii_digit() || *c == '.')
-                    .collect::<String>()
-                    .parse::<f64>()
-                    .unwrap_or_default()
-            })
-            .map(|rating| rating * 2.0)
-        {
+        if let Some(rating) = item.rating {
             nfo.set_rating(rating);
         }
-
-        if let Some(title) = item
-            .select(&self.selectors.home_title)
-            .next()
-            .and_then(|node| node.attr("title"))
-        {
-            nfo.set_title(title.to_string());
+        if let Some(title) = item.title {
+            nfo.set_title(title);
         }
 
-        item.select(&self.selectors.home_title)
-            .next()
-            .and_then(|node| {
-                node.attr("href")
-                    .map(|href| format!("{}{href}", self.base_url))
+        item.url.ok_or_else(|| anyhow!("detail url not found"))
+    }
+
+    fn parse_home_items(&self, key: &VideoType, text: &str) -> Vec<HomeItem> {
+        let html = Html::parse_document(text);
+
+        let name = key.to_string();
+        html.select(&self.selectors.home_item)
+            .filter(|node| {
+                node.select(&self.selectors.home_item_id)
+                    .next()
+                    .map(|node| node.text().collect::<String>() == name)
+                    .unwrap_or(false)
+            })
+            .map(|item| {
+                let date = item
+                    .select(&self.selectors.home_date)
+                    .next()
+                    .map(|node| node.text().collect::<String>().trim().to_string());
+
+                let rating = item
+                    .select(&self.selectors.home_rating)
+                    .next()
+                    .and_then(|node| node.text().last().map(|text| text.trim()))
+                    .map(|text| {
+                        text.chars()
+                            .take_while(|c| c.is_ascii_digit() || *c == '.')
+                            .collect::<String>()
+                            .parse::<f64>()
+                            .unwrap_or_default()
+                    })
+                    .map(|rating| rating * 2.0);
+
+                let title = item
+                    .select(&self.selectors.home_title)
+                    .next()
+                    .and_then(|node| node.attr("title"))
+                    .map(|title| title.to_string());
+
+                let url = item
+                    .select(&self.selectors.home_title)
+                    .next()
+                    .and_then(|node| {
+                        node.attr("href")
+                            .map(|href| format!("{}{href}", self.base_url))
+                    });
+
+                HomeItem {
+                    title,
+                    date,
+                    rating,
+                    url,
+                }
             })
-            .ok_or_else(|| anyhow!("detail url not found"))
+            .collect()
     }
 
     async fn find_detail(&self, url: &str, nfo: &mut Nfo) -> Result<()> {
@@ -6,6 +6,7 @@ mod hbox;
 mod jav321;
 mod javdb;
 mod missav;
+mod plugin;
 mod porny;
 mod subtitle_cat;
 mod the_porn_db;
@@ -15,7 +16,7 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use airav::Airav;
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
 use async_trait::async_trait;
 use avsox::Avsox;
 use cable::Cable;
@@ -27,6 +28,7 @@ use javdb::Javdb;
 use log::{error, warn};
 use missav::Missav;
 use nfo::{Country, Nfo};
+use plugin::Plugin;
 use porny::Porny;
 use subtitle_cat::SubtitleCat;
 use the_porn_db::ThePornDB;
@@ -38,12 +40,48 @@ trait Finder: Send + Sync + Display {
     async fn find(&self, key: &VideoType) -> Result<Nfo>;
 }
 
+/// one of the hits when a search is ambiguous
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub title: String,
+    pub date: String,
+    pub source: String,
+}
+
+/// choose one of the candidates, the first one is used if nothing picked
+#[async_trait]
+pub trait Picker: Send + Sync {
+    async fn pick(&self, key: &VideoType, candidates: &[Candidate]) -> Option<usize>;
+}
+
+async fn pick<T: Send>(
+    picker: Option<&Arc<dyn Picker>>,
+    key: &VideoType,
+    mut items: Vec<(Candidate, T)>,
+) -> Option<T> {
+    if let Some(picker) = picker
+        && items.len() > 1
+    {
+        let candidates = items
+            .iter()
+            .map(|(candidate, _)| candidate.clone())
+            .collect::<Vec<_>>();
+        if let Some(idx) = picker.pick(key, &candidates).await
+            && idx < items.len()
+        {
+            return Some(items.swap_remove(idx).1);
+        }
+    }
+
+    items.into_iter().next().map(|(_, item)| item)
+}
+
 pub struct Spider {
     finders: Vec<Arc<dyn Finder>>,
 }
 
 impl Spider {
-    pub fn new(config: &Config) -> Result<Spider> {
+    pub fn new(config: &Config, picker: Option<Arc<dyn Picker>>) -> Result<Spider> {
         let timeout = Duration::from_secs(config.network.timeout);
         let proxy = &config.network.proxy;
         let url = &config.url;
@@ -59,6 +97,17 @@ impl Spider {
                         .with_context(|| concat!("build ", $m))?,
                 )
             };
+            ($s:ty, $u:expr, $m:expr, picker) => {
+                Arc::new(
+                    <$s>::builder()
+                        .maybe_base_url($u)
+                        .timeout(timeout)
+                        .maybe_proxy(proxy.clone())
+                        .maybe_picker(picker.clone())
+                        .build()
+                        .with_context(|| concat!("build ", $m))?,
+                )
+            };
         }
 
         let mut finders: Vec<Arc<dyn Finder>> = vec![
@@ -68,10 +117,15 @@ impl Spider {
             spider!(Fc2ppvDB, url.fc2ppv_db.clone(), "fc2ppv db"),
             spider!(Hbox, url.hbox.clone(), "hbox"),
             spider!(Jav321, url.jav321.clone(), "jav321"),
-            spider!(Javdb, url.javdb.clone(), "javdb"),
+            spider!(Javdb, url.javdb.clone(), "javdb", picker),
             spider!(Missav, url.missav.clone(), "missav"),
-            spider!(Porny, url.porny.clone(), "91 porny"),
-            spider!(SubtitleCat, url.subtitle_cat.clone(), "subtitle cat"),
+            spider!(Porny, url.porny.clone(), "91 porny", picker),
+            spider!(
+                SubtitleCat,
+                url.subtitle_cat.clone(),
+                "subtitle cat",
+                picker
+            ),
         ];
         if let Some(ref key) = config.the_porn_db.key {
             finders.push(Arc::new(
@@ -81,16 +135,99 @@ impl Spider {
                     .key(key)
                     .timeout(timeout)
                     .maybe_proxy(proxy.clone())
+                    .maybe_picker(picker.clone())
                     .build()
                     .with_context(|| "build the porn db")?,
             ));
         }
 
+        for plugin in config.plugins.iter() {
+            if finders
+                .iter()
+                .any(|finder| finder.to_string().eq_ignore_ascii_case(&plugin.name))
+            {
+                bail!("plugin {} has the same name as another finder", plugin.name);
+            }
+            let timeout = plugin.timeout.map(Duration::from_secs).unwrap_or(timeout);
+            finders.push(Arc::new(
+                Plugin::builder()
+                    .name(&plugin.name)
+                    .command(plugin.command.clone())
+                    .timeout(timeout)
+                    .maybe_proxy(proxy.clone())
+                    .build()
+                    .with_context(|| format!("build plugin {}", plugin.name))?,
+            ));
+        }
+
         let spider = Spider { finders };
         Ok(spider)
     }
 
+    pub fn names(&self) -> Vec<String> {
+        self.finders
+            .iter()
+            .map(|finder| finder.to_string())
+            .collect()
+    }
+
+    /// a spider with only the finders of given names, names are case insensitive
+    pub fn only(&self, names: &[String]) -> Result<Spider> {
+        self.check_names(names)?;
+        let finders = self
+            .finders
+            .iter()
+            .filter(|finder| names.is_empty() || Self::is_named(finder, names))
+            .cloned()
+            .collect();
+
+        Ok(Spider { finders })
+    }
+
+    /// a spider without the finders of given names, names are case insensitive
+    pub fn except(&self, names: &[String]) -> Result<Spider> {
+        self.check_names(names)?;
+        let finders = self
+            .finders
+            .iter()
+            .filter(|finder| !Self::is_named(finder, names))
+            .cloned()
+            .collect();
+
+        Ok(Spider { finders })
+    }
+
+    fn check_names(&self, names: &[String]) -> Result<()> {
+        if let Some(unknown) = names.iter().find(|name| {
+            !self
+                .finders
+                .iter()
+                .any(|finder| finder.to_string().eq_ignore_ascii_case(name))
+        }) {
+            bail!(
+                "finder {unknown} not found, available: {}",
+                self.names().join(", ")
+            );
+        }
+
+        Ok(())
+    }
+
+    fn is_named(finder: &Arc<dyn Finder>, names: &[String]) -> bool {
+        names
+            .iter()
+            .any(|name| finder.to_string().eq_ignore_ascii_case(name))
+    }
+
     pub async fn find(&self, key: VideoType) -> Result<Nfo> {
+        let name = key.to_string();
+        self.search(key)
+            .await?
+            .nfo
+            .ok_or_else(|| anyhow!("could not find anything about {name} in all finders"))
+    }
+
+    pub async fn search(&self, key: VideoType) -> Result<Found> {
         let key = Arc::new(key);
         let mut tasks = Vec::new();
         for finder in self.finders.iter() {
@@ -99,6 +236,7 @@ impl Spider {
                 continue;
             }
 
+            let name = finder.to_string();
             let finder = finder.clone();
             let key = key.clone();
             let task = tokio::spawn(async move {
@@ -107,24 +245,41 @@ impl Spider {
                     .await
                     .with_context(|| format!("in finder {finder}"))
             });
-            tasks.push(task);
+            tasks.push((name, task));
         }
 
-        let mut nfo = None;
-        for task in tasks {
+        let mut found = Found {
+            nfo: None,
+            succeed: Vec::new(),
+            failed: Vec::new(),
+        };
+        for (name, task) in tasks {
             match task.await? {
-                Ok(found_nfo) => match nfo {
-                    None => nfo = Some(found_nfo),
-                    Some(ref mut nfo) => nfo.merge(found_nfo),
-                },
-                Err(err) => error!("could not find {key}, caused by {err:?}"),
+                Ok(found_nfo) => {
+                    found.succeed.push(name);
+                    match found.nfo {
+                        None => found.nfo = Some(found_nfo),
+                        Some(ref mut nfo) => nfo.merge(found_nfo),
+                    }
+                }
+                Err(err) => {
+                    error!("could not find {key}, caused by {err:?}");
+                    found.failed.push((name, format!("{err:?}")));
+                }
             }
         }
 
-        nfo.ok_or_else(|| anyhow!("could not find anything about {key} in all finders"))
+        Ok(found)
     }
 }
 
+/// merged result of all finders, with the finders succeed or failed
+pub struct Found {
+    pub nfo: Option<Nfo>,
+    pub succeed: Vec<String>,
+    pub failed: Vec<(String, String)>,
+}
+
 fn which_country(key: &VideoType) -> Country {
     match key {
         VideoType::Jav(id, _) => match id.as_str() {
@@ -166,3 +321,48 @@ macro_rules! select {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    struct Fixed(Option<usize>);
+
+    #[async_trait]
+    impl Picker for Fixed {
+        async fn pick(&self, _key: &VideoType, _candidates: &[Candidate]) -> Option<usize> {
+            self.0
+        }
+    }
+
+    fn items() -> Vec<(Candidate, &'static str)> {
+        ["first", "second"]
+            .into_iter()
+            .map(|title| {
+                let candidate = Candidate {
+                    title: title.to_string(),
+                    date: String::new(),
+                    source: "test".to_string(),
+                };
+                (candidate, title)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_pick() {
+        let key = VideoType::Jav("STARS".to_string(), "804".to_string());
+        let cases = [
+            (None, "first"),
+            (Some(Fixed(None)), "first"),
+            (Some(Fixed(Some(1))), "second"),
+            (Some(Fixed(Some(5))), "first"),
+        ];
+        for (picker, expected) in cases {
+            let picker = picker.map(|picker| Arc::new(picker) as Arc<dyn Picker>);
+            let actual = pick(picker.as_ref(), &key, items()).await;
+            assert_eq!(actual, Some(expected));
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use std::fmt::{self, Display};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use async_trait::async_trait;
+use bon::bon;
+use http_client::Client;
+use log::info;
+use nfo::{Country, Mpaa, Nfo};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::time;
+use video::VideoType;
+
+use super::Finder;
+
+/// a finder run as an external command, the key is written to stdin and the
+/// found fields are read from stdout, both as json
+pub struct Plugin {
+    name: String,
+    command: Vec<String>,
+    timeout: Duration,
+    client: Client,
+}
+
+#[bon]
+impl Plugin {
+    #[builder]
+    pub fn new(
+        name: impl Into<String>,
+        command: Vec<String>,
+        timeout: Duration,
+        proxy: Option<String>,
+    ) -> Result<Plugin> {
+        if command.is_empty() {
+            bail!("empty command");
+        }
+        let client = Client::builder()
+            .timeout(timeout)
+            .interval(1)
+            .maybe_proxy(proxy)
+            .build()
+            .with_context(|| "build http client")?;
+
+        let plugin = Plugin {
+            name: name.into(),
+            command,
+            timeout,
+            client,
+        };
+        Ok(plugin)
+    }
+
+    async fn call(&self, key: &VideoType) -> Result<Output> {
+        let input = serde_json::to_vec(&Input::from(key)).with_context(|| "encode key")?;
+        let mut child = Command::new(&self.command[0])
+            .args(&self.command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("spawn {}", self.command.join(" ")))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // the command may exit without reading anything
+            stdin.write_all(&input).await.ok();
+        }
+
+        let output = time::timeout(self.timeout, child.wait_with_output())
+            .await
+            .with_context(|| format!("timeout after {} seconds", self.timeout.as_secs()))?
+            .with_context(|| "wait for command")?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!("exit with {}, {}", output.status, stderr.trim());
+        }
+
+        serde_json::from_slice(&output.stdout).with_context(|| "decode output")
+    }
+
+    /// url is downloaded, anything else is read as a file
+    async fn load(&self, location: &str) -> Result<Vec<u8>> {
+        if location.starts_with("http://") || location.starts_with("https://") {
+            let bytes = self
+                .client
+                .wait()
+                .await
+                .get(location)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+            return Ok(bytes.to_vec());
+        }
+
+        fs::read(Path::new(location))
+            .await
+            .with_context(|| format!("read {location}"))
+    }
+}
+
+impl Display for Plugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[async_trait]
+impl Finder for Plugin {
+    fn support(&self, _key: &VideoType) -> bool {
+        true
+    }
+
+    async fn find(&self, key: &VideoType) -> Result<Nfo> {
+        let output = self.call(key).await.with_context(|| "call plugin")?;
+        let mut nfo = Nfo::builder()
+            .id(key)
+            .maybe_country(output.country.map(Country::from))
+            .maybe_mpaa(output.mpaa.map(Mpaa::from))
+            .build();
+
+        if let Some(title) = output.title {
+            nfo.set_title(title);
+        }
+        if let Some(plot) = output.plot {
+            nfo.set_plot(plot);
+        }
+        if let Some(rating) = output.rating {
+            nfo.set_rating(rating);
+        }
+        if let Some(runtime) = output.runtime {
+            nfo.set_runtime(runtime);
+        }
+        if let Some(director) = output.director {
+            nfo.set_director(director);
+        }
+        if let Some(premiered) = output.premiered {
+            nfo.set_premiered(premiered);
+        }
+        if let Some(studio) = output.studio {
+            nfo.set_studio(studio);
+        }
+        nfo.genres_mut().extend(output.genres);
+        nfo.actors_mut().extend(output.actors);
+        if let Some(ref poster) = output.poster {
+            let poster = self.load(poster).await.with_context(|| "load poster")?;
+            nfo.set_poster(poster);
+        }
+        if let Some(ref fanart) = output.fanart {
+            let fanart = self.load(fanart).await.with_context(|| "load fanart")?;
+            nfo.set_fanart(fanart);
+        }
+        if let Some(ref subtitle) = output.subtitle {
+            let subtitle = self.load(subtitle).await.with_context(|| "load subtitle")?;
+            nfo.set_subtitle(subtitle);
+        }
+
+        info!("{nfo:?}");
+        Ok(nfo)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Input {
+    name: String,
+    #[serde(rename = "type")]
+    ty: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    number: Option<String>,
+}
+
+impl From<&VideoType> for Input {
+    fn from(key: &VideoType) -> Input {
+        let (ty, id, number) = match key {
+            VideoType::Jav(id, number) => ("jav", Some(id.clone()), Some(number.clone())),
+            VideoType::Fc2(number) => ("fc2", None, Some(number.clone())),
+            VideoType::Other(_) => ("other", None, None),
+        };
+
+        Input {
+            name: key.to_string(),
+            ty,
+            id,
+            number,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Output {
+    title: Option<String>,
+    plot: Option<String>,
+    rating: Option<f64>,
+    runtime: Option<u32>,
+    mpaa: Option<OutputMpaa>,
+    #[serde(default)]
+    genres: Vec<String>,
+    country: Option<OutputCountry>,
+    director: Option<String>,
+    premiered: Option<String>,
+    studio: Option<String>,
+    #[serde(default)]
+    actors: Vec<String>,
+    poster: Option<String>,
+    fanart: Option<String>,
+    subtitle: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+enum OutputCountry {
+    #[serde(rename = "japan")]
+    Japan,
+
+    #[serde(rename = "china")]
+    China,
+}
+
+impl From<OutputCountry> for Country {
+    fn from(country: OutputCountry) -> Country {
+        match country {
+            OutputCountry::Japan => Country::Japan,
+            OutputCountry::China => Country::China,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+enum OutputMpaa {
+    #[serde(rename = "G")]
+    G,
+
+    #[serde(rename = "PG")]
+    PG,
+
+    #[serde(rename = "PG-13")]
+    PG13,
+
+    #[serde(rename = "R")]
+    R,
+
+    #[serde(rename = "NC-17")]
+    NC17,
+}
+
+impl From<OutputMpaa> for Mpaa {
+    fn from(mpaa: OutputMpaa) -> Mpaa {
+        match mpaa {
+            OutputMpaa::G => Mpaa::G,
+            OutputMpaa::PG => Mpaa::PG,
+            OutputMpaa::PG13 => Mpaa::PG13,
+            OutputMpaa::R => Mpaa::R,
+            OutputMpaa::NC17 => Mpaa::NC17,
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn finder(script: &str) -> Result<Plugin> {
+        Plugin::builder()
+            .name("test")
+            .command(vec!["sh".to_string(), "-c".to_string(), script.to_string()])
+            .timeout(Duration::from_secs(5))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_find() -> Result<()> {
+        let finder = finder(concat!(
+            "read key; case \"$key\" in ",
+            r#"'{"name":"STARS-804","type":"jav","id":"STARS","number":"804"}') "#,
+            r#"echo '{"title": "t", "runtime": 120, "country": "japan", "actors": ["a", "b"]}';; "#,
+            "*) exit 1;; esac",
+        ))?;
+        let key = VideoType::Jav("STARS".to_string(), "804".to_string());
+        let nfo = finder.find(&key).await?;
+
+        let mut expected = Nfo::builder()
+            .id("STARS-804")
+            .country(Country::Japan)
+            .build();
+        expected.set_title("t".to_string());
+        expected.set_runtime(120);
+        expected
+            .actors_mut()
+            .extend(["a".to_string(), "b".to_string()]);
+        assert_eq!(nfo, expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_find_failed() -> Result<()> {
+        let key = VideoType::Fc2("3061625".to_string());
+        let cases = [
+            ("echo 'not found' >&2; exit 1", "not found"),
+            ("echo 'not json'", "decode output"),
+            ("sleep 10", "timeout"),
+        ];
+        for (script, expected) in cases {
+            let finder = finder(script)?;
+            let err = finder.find(&key).await.err().map(|e| format!("{e:#}"));
+            assert!(
+                err.as_ref().is_some_and(|err| err.contains(expected)),
+                "{err:?} should contain {expected}"
+            );
+        }
+
+        Ok(())
+    }
+}
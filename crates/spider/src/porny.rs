@@ -1,4 +1,5 @@
 use std::fmt::{self, Display};
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{Context, Result, anyhow, bail};
@@ -10,7 +11,7 @@ use nfo::{Country, Mpaa, Nfo};
 use scraper::Html;
 use video::VideoType;
 
-use super::{Finder, select};
+use super::{Candidate, Finder, Picker, pick, select};
 
 const HOST: &str = "https://91porny.com";
 
@@ -23,10 +24,19 @@ select!(
     fanart: "div > a.display.d-block > div.img"
 );
 
+struct Item {
+    title: String,
+    author: Option<String>,
+    date: Option<String>,
+    runtime: Option<u32>,
+    fanart: Option<String>,
+}
+
 pub struct Porny {
     base_url: String,
     client: Client,
     selectors: Selectors,
+    picker: Option<Arc<dyn Picker>>,
 }
 
 #[bon]
@@ -36,6 +46,7 @@ impl Porny {
         base_url: Option<String>,
         timeout: Duration,
         proxy: Option<String>,
+        picker: Option<Arc<dyn Picker>>,
     ) -> Result<Porny> {
         let client = Client::builder()
             .timeout(timeout)
@@ -53,6 +64,7 @@ impl Porny {
             base_url,
             selectors,
             client,
+            picker,
         };
         Ok(porny)
     }
@@ -114,81 +126,101 @@ impl Porny {
             .await?
             .text()
             .await?;
-        let html = Html::parse_document(&text);
-
-        let Some(found) = html.select(&self.selectors.item).find(|item| {
-            item.select(&self.selectors.title)
-                .next()
-                .map(|title| title.text().collect::<String>())
-                .map(|title| title == name)
-                .unwrap_or(false)
-        }) else {
+        let items = self.parse_items(&name, &text);
+        let candidates = items
+            .into_iter()
+            .map(|item| {
+                let candidate = Candidate {
+                    title: item.title.clone(),
+                    date: item.date.clone().unwrap_or_default(),
+                    source: self.to_string(),
+                };
+                (candidate, item)
+            })
+            .collect();
+        let Some(found) = pick(self.picker.as_ref(), key, candidates).await else {
             bail!("item not found");
         };
 
-        if let Some(title) = found
-            .select(&self.selectors.title)
-            .next()
-            .map(|title| title.text().collect::<String>())
-        {
-            nfo.set_title(title);
-        }
-
-        if let Some(author) = found
-            .select(&self.selectors.author)
-            .next()
-            .map(|author| author.text().collect::<String>())
-        {
+        nfo.set_title(found.title);
+        if let Some(author) = found.author {
             nfo.set_director(author);
         }
-
-        if let Some(date) = found
-            .select(&self.selectors.date)
-            .next()
-            .map(|date| date.text().collect::<String>())
-            .and_then(|date| {
-                date.split_once('|')
-                    .map(|(date, _)| date.trim().to_string())
-            })
-        {
+        if let Some(date) = found.date {
             nfo.set_premiered(date);
         }
+        if let Some(runtime) = found.runtime {
+            nfo.set_runtime(runtime);
+        }
 
-        if let Some(runtime) =
-            found
-                .select(&self.selectors.runtime)
-                .next()
-                .map(|runtime| runtime.text().collect::<String>())
-                .map(|runtime| {
-                    runtime.trim().split(':').take(2).enumerate().fold(
-                        0,
-                        |mut runtime, (idx, num)| {
-                            let num = num.parse().unwrap_or(0);
-
-                            match idx {
-                                0 => {
-                                    runtime += num * 60;
-                                }
-                                1 => {
-                                    runtime += num;
+        found.fanart.ok_or(anyhow!("fanart not found"))
+    }
+
+    fn parse_items(&self, name: &str, text: &str) -> Vec<Item> {
+        let html = Html::parse_document(text);
+
+        html.select(&self.selectors.item)
+            .filter_map(|found| {
+                let title = found
+                    .select(&self.selectors.title)
+                    .next()
+                    .map(|title| title.text().collect::<String>())
+                    .filter(|title| title == name)?;
+
+                let author = found
+                    .select(&self.selectors.author)
+                    .next()
+                    .map(|author| author.text().collect::<String>());
+
+                let date = found
+                    .select(&self.selectors.date)
+                    .next()
+                    .map(|date| date.text().collect::<String>())
+                    .and_then(|date| {
+                        date.split_once('|')
+                            .map(|(date, _)| date.trim().to_string())
+                    });
+
+                let runtime = found
+                    .select(&self.selectors.runtime)
+                    .next()
+                    .map(|runtime| runtime.text().collect::<String>())
+                    .map(|runtime| {
+                        runtime.trim().split(':').take(2).enumerate().fold(
+                            0,
+                            |mut runtime, (idx, num)| {
+                                let num = num.parse().unwrap_or(0);
+
+                                match idx {
+                                    0 => {
+                                        runtime += num * 60;
+                                    }
+                                    1 => {
+                                        runtime += num;
+                                    }
+                                    _ => {}
                                 }
-                                _ => {}
-                            }
 
-                            runtime
-                        },
-                    )
-                })
-        {
-            nfo.set_runtime(runtime);
-        }
+                                runtime
+                            },
+                        )
+                    });
+
+                let fanart = found
+                    .select(&self.selectors.fanart)
+                    .next()
+                    .and_then(|img| img.attr("style"))
+                    .and_then(|sty| sty.split("'").nth(1).map(|fanart| fanart.to_string()));
 
-        found
-            .select(&self.selectors.fanart)
-            .next()
-            .and_then(|img| img.attr("style"))
-            .and_then(|sty| sty.split("'").nth(1).map(|fanart| fanart.to_string()))
-            .ok_or(anyhow!("fanart not found"))
+                Some(Item {
+                    title,
+                    author,
+                    date,
+                    runtime,
+                    fanart,
+                })
+            })
+            .collect()
     }
 }
 
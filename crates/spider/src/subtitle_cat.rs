@@ -1,4 +1,5 @@
 use std::fmt::{self, Display};
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{Context, Result, anyhow, bail};
@@ -10,7 +11,7 @@ use nfo::Nfo;
 use scraper::Html;
 use video::VideoType;
 
-use super::{Finder, select};
+use super::{Candidate, Finder, Picker, pick, select};
 
 const HOST: &str = "https://www.subtitlecat.com";
 
@@ -23,6 +24,7 @@ pub struct SubtitleCat {
     base_url: String,
     client: Client,
     selectors: Selectors,
+    picker: Option<Arc<dyn Picker>>,
 }
 
 #[bon]
@@ -32,6 +34,7 @@ impl SubtitleCat {
         base_url: Option<String>,
         timeout: Duration,
         proxy: Option<String>,
+        picker: Option<Arc<dyn Picker>>,
     ) -> Result<SubtitleCat> {
         let client = Client::builder()
             .timeout(timeout)
@@ -49,6 +52,7 @@ impl SubtitleCat {
             base_url,
             client,
             selectors,
+            picker,
         };
         Ok(subtitle_cat)
     }
@@ -131,7 +135,6 @@ impl SubtitleCat {
             .await?
             .text()
             .await?;
-        let html = Html::parse_document(&text);
         let possible_names = match &key {
             VideoType::Jav(id, number) => {
                 vec![format!("{id}-{number}"), format!("{id}{number}")]
@@ -144,16 +147,30 @@ impl SubtitleCat {
             ],
             VideoType::Other(title) => vec![title.clone()],
         };
+        let candidates = {
+            let html = Html::parse_document(&text);
+            html.select(&self.selectors.home_item)
+                .filter_map(|item| {
+                    let title = item.text().collect::<String>();
+                    if !possible_names.iter().any(|name| title.contains(name)) {
+                        return None;
+                    }
+
+                    let url = item
+                        .attr("href")
+                        .map(|href| format!("{}/{href}", self.base_url))?;
+                    let candidate = Candidate {
+                        title: title.trim().to_string(),
+                        date: String::new(),
+                        source: self.to_string(),
+                    };
+                    Some((candidate, url))
+                })
+                .collect::<Vec<_>>()
+        };
 
-        html.select(&self.selectors.home_item)
-            .find(|item| {
-                let title = item.text().collect::<String>();
-                possible_names.iter().any(|name| title.contains(name))
-            })
-            .and_then(|node| {
-                node.attr("href")
-                    .map(|href| format!("{}/{href}", self.base_url))
-            })
+        pick(self.picker.as_ref(), key, candidates)
+            .await
             .ok_or_else(|| anyhow!("subtitle not found"))
     }
 }
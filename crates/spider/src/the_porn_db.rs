@@ -1,4 +1,5 @@
 use std::fmt::{self, Display};
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{Context, Result, anyhow};
@@ -13,7 +14,7 @@ use serde::Deserialize;
 use serde_json::Value;
 use video::VideoType;
 
-use super::{Finder, select, which_country};
+use super::{Candidate, Finder, Picker, pick, select, which_country};
 
 const HOST: &str = "https://theporndb.net";
 const API_HOST: &str = "https://api.theporndb.net";
@@ -27,6 +28,7 @@ pub struct ThePornDB {
     api_url: String,
     client: Client,
     selectors: Selectors,
+    picker: Option<Arc<dyn Picker>>,
 }
 
 #[bon]
@@ -38,6 +40,7 @@ impl ThePornDB {
         key: impl AsRef<str>,
         timeout: Duration,
         proxy: Option<String>,
+        picker: Option<Arc<dyn Picker>>,
     ) -> Result<Self> {
         let headers = {
             let mut headers = HeaderMap::new();
@@ -75,6 +78,7 @@ impl ThePornDB {
             api_url,
             client,
             selectors,
+            picker,
         };
         Ok(this)
     }
@@ -358,20 +362,34 @@ impl ThePornDB {
             .await?
             .text()
             .await?;
-        let html = Html::parse_document(&text);
-        let data = html
-            .select(&self.selectors.data)
-            .next()
-            .and_then(|app| app.attr("data-page"))
-            .ok_or(anyhow!("data-page attribute not found"))?;
-        let res = serde_json::from_str::<Response>(data).with_context(|| "parse data to json")?;
+        let res = {
+            let html = Html::parse_document(&text);
+            let data = html
+                .select(&self.selectors.data)
+                .next()
+                .and_then(|app| app.attr("data-page"))
+                .ok_or(anyhow!("data-page attribute not found"))?;
+            serde_json::from_str::<Response>(data).with_context(|| "parse data to json")?
+        };
 
-        res.props
+        let candidates = res
+            .props
             .scenes
             .data
             .into_iter()
-            .find(|data| data.title.contains(&name))
-            .map(|data| data.link)
+            .filter(|data| data.title.contains(&name))
+            .map(|data| {
+                let candidate = Candidate {
+                    title: data.title,
+                    date: data.date.as_str().unwrap_or_default().to_string(),
+                    source: self.to_string(),
+                };
+                (candidate, data.link)
+            })
+            .collect();
+
+        pick(self.picker.as_ref(), key, candidates)
+            .await
             .ok_or(anyhow!("data not found"))
     }
 